@@ -3,8 +3,10 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use cw_quadratic_funding::msg::{HandleMsg, InitMsg, QueryMsg};
-use cw_quadratic_funding::state::{Config, Proposal, Vote};
+use cw_quadratic_funding::msg::{
+    HandleMsg, InitMsg, MigrateMsg, OracleQueryMsg, PriceResponse, QueryMsg, RelayedVotePayload,
+};
+use cw_quadratic_funding::state::{Config, DistributionResult, Proposal, Vote};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -15,7 +17,12 @@ fn main() {
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(OracleQueryMsg), &out_dir);
+    export_schema(&schema_for!(PriceResponse), &out_dir);
+    export_schema(&schema_for!(RelayedVotePayload), &out_dir);
     export_schema(&schema_for!(Config), &out_dir);
     export_schema(&schema_for!(Proposal), &out_dir);
     export_schema(&schema_for!(Vote), &out_dir);
+    export_schema(&schema_for!(DistributionResult), &out_dir);
 }
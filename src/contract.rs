@@ -1,14 +1,45 @@
 use cosmwasm_std::{
-    attr, coin, to_binary, BankMsg, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env,
-    HandleResponse, HumanAddr, InitResponse, MessageInfo, Order, StdResult,
+    attr, coin, from_slice, to_binary, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Decimal,
+    Deps, DepsMut, Empty, Env, HandleResponse, HumanAddr, InitResponse, MessageInfo,
+    MigrateResponse, Order, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
 
 use crate::error::ContractError;
-use crate::helper::extract_budget_coin;
-use crate::matching::{calculate_clr, QuadraticFundingAlgorithm, RawGrant};
-use crate::msg::{AllProposalsResponse, HandleMsg, InitMsg, QueryMsg};
-use crate::state::{proposal_seq, Config, Proposal, Vote, CONFIG, PROPOSALS, VOTES};
+use crate::factory::msg::HandleMsg as FactoryHandleMsg;
+use crate::helper::{
+    add_coin, commitment_hash, extend_period, extract_budget_coin, extract_coin, split_payout,
+    validate_denom_rates, validate_proposal_fields, validate_recipients, validate_reveal_period,
+    verify_signature,
+};
+use crate::matching::{
+    calculate_clr, calculate_clr_breakdown, Contribution, QuadraticFundingAlgorithm, RawGrant,
+    MAX_VOTER_SCORE, WEIGHT_SCALE,
+};
+use crate::migrations::migrate_legacy_state;
+use crate::msg::{
+    AcceptedDenomsResponse, AllProposalsResponse, Allocation, DelegationsResponse, DenomRate,
+    HandleMsg, InitMsg, MigrateMsg, OracleQueryMsg, PriceResponse, ProposalMatchBreakdownResponse,
+    QueryMsg, RecipientShare, RelayAccountResponse, RelayedVotePayload, RolloversResponse,
+    VoterScore, VotesResponse, WhitelistMembersResponse,
+};
+use crate::state::{
+    proposal_seq, Commitment, Config, Delegation, DistributionResult, Grant, PausedAt, Proposal,
+    ProposalStatus, Recipient, Rollover, Vote, VoterWeightSource, Whitelist, BASIS_POINTS,
+    COMMITMENTS, CONFIG, CREATE_PROPOSAL_WHITELIST, DEFAULT_DESCRIPTION_LENGTH,
+    DEFAULT_MAX_RECIPIENTS, DEFAULT_METADATA_LENGTH, DEFAULT_TITLE_LENGTH, DELEGATIONS,
+    DENOM_RATES, DEPOSITS, DISTRIBUTION_RESULTS, PROPOSALS, RELAY_NONCES, ROLLOVERS, VOTER_SCORES,
+    VOTER_VOTES, VOTES, VOTE_PROPOSAL_WHITELIST,
+};
 use cosmwasm_storage::nextval;
+use cw0::Expiration;
+use cw2::{set_contract_version, CONTRACT};
+use cw4::{Cw4QueryMsg, MemberResponse};
+use cw_storage_plus::{Bound, PkOwned};
+use semver::Version;
+
+// version info for migration
+const CONTRACT_NAME: &str = "crates.io:cw-quadratic-funding";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // Note, you can use StdResult in some functions where you do not
 // make use of the custom errors
@@ -19,37 +50,78 @@ pub fn init(
     msg: InitMsg,
 ) -> Result<InitResponse, ContractError> {
     msg.validate(env)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let budget = extract_budget_coin(info.sent_funds.as_slice(), &msg.budget_denom)?;
-    let mut create_proposal_whitelist: Option<Vec<CanonicalAddr>> = None;
-    let mut vote_proposal_whitelist: Option<Vec<CanonicalAddr>> = None;
-    if let Some(pwl) = msg.create_proposal_whitelist {
-        let mut tmp_wl = vec![];
-        for w in pwl {
-            tmp_wl.push(deps.api.canonical_address(&w)?)
-        }
-        create_proposal_whitelist = Some(tmp_wl);
+    let create_proposal_whitelist_enabled = msg.create_proposal_whitelist.is_some();
+    for w in msg.create_proposal_whitelist.unwrap_or_default() {
+        let addr = deps.api.canonical_address(&w)?;
+        CREATE_PROPOSAL_WHITELIST.save(deps.storage, addr.as_slice(), &Empty {})?;
     }
-    if let Some(vwl) = msg.vote_proposal_whitelist {
-        let mut tmp_wl = vec![];
-        for w in vwl {
-            tmp_wl.push(deps.api.canonical_address(&w)?)
-        }
-        vote_proposal_whitelist = Some(tmp_wl);
+    let vote_proposal_whitelist_enabled = msg.vote_proposal_whitelist.is_some();
+    for w in msg.vote_proposal_whitelist.unwrap_or_default() {
+        let addr = deps.api.canonical_address(&w)?;
+        VOTE_PROPOSAL_WHITELIST.save(deps.storage, addr.as_slice(), &Empty {})?;
+    }
+    let accepted_denoms = msg.accepted_denoms.unwrap_or_default();
+    validate_denom_rates(&msg.budget_denom, &accepted_denoms)?;
+    for r in accepted_denoms {
+        DENOM_RATES.save(deps.storage, r.denom.as_bytes(), &r.rate)?;
     }
     let cfg = Config {
-        admin: deps.api.canonical_address(&msg.admin)?,
+        admin: Some(deps.api.canonical_address(&msg.admin)?),
+        pending_admin: None,
         leftover_addr: deps.api.canonical_address(&msg.leftover_addr)?,
-        create_proposal_whitelist,
-        vote_proposal_whitelist,
+        create_proposal_whitelist_enabled,
+        vote_proposal_whitelist_enabled,
+        proposer_group: msg
+            .proposer_group
+            .map(|g| deps.api.canonical_address(&g))
+            .transpose()?,
+        voter_group: msg
+            .voter_group
+            .map(|g| deps.api.canonical_address(&g))
+            .transpose()?,
+        voter_weight: msg.voter_weight,
+        min_contribution: msg.min_contribution.unwrap_or_default(),
+        min_unique_contributors: msg.min_unique_contributors.unwrap_or_default(),
+        max_matched_contribution: msg.max_matched_contribution,
         voting_period: msg.voting_period,
         proposal_period: msg.proposal_period,
         algorithm: msg.algorithm,
         budget,
+        title_length: msg.title_length.unwrap_or(DEFAULT_TITLE_LENGTH),
+        description_length: msg.description_length.unwrap_or(DEFAULT_DESCRIPTION_LENGTH),
+        metadata_length: msg.metadata_length.unwrap_or(DEFAULT_METADATA_LENGTH),
+        proposal_deposit: msg.proposal_deposit,
+        paused: false,
+        paused_at: None,
+        rollover_leftover: msg.rollover_leftover.unwrap_or_default(),
+        round: 1,
+        distributed: false,
+        oracle: msg
+            .oracle
+            .map(|o| deps.api.canonical_address(&o))
+            .transpose()?,
+        commit_reveal: msg.commit_reveal,
+        max_recipients: msg.max_recipients.unwrap_or(DEFAULT_MAX_RECIPIENTS),
     };
     CONFIG.save(deps.storage, &cfg)?;
 
-    Ok(InitResponse::default())
+    // notify factory of the new round's address
+    let mut messages = vec![];
+    if let Some(factory) = msg.factory {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: factory,
+            msg: to_binary(&FactoryHandleMsg::RegisterRound {})?,
+            send: vec![],
+        }));
+    }
+
+    Ok(InitResponse {
+        messages,
+        ..Default::default()
+    })
 }
 
 // And declare a custom Error variant for the ones where you will want to make use of it
@@ -59,20 +131,122 @@ pub fn handle(
     info: MessageInfo,
     msg: HandleMsg,
 ) -> Result<HandleResponse, ContractError> {
+    // paused contract only accepts the pause switch
+    let paused = CONFIG.load(deps.storage)?.paused;
+    if paused && !matches!(msg, HandleMsg::Pause {} | HandleMsg::Unpause {}) {
+        return Err(ContractError::Paused {});
+    }
+
     match msg {
         HandleMsg::CreateProposal {
             title,
             description,
             metadata,
             fund_address,
-        } => handle_create_proposal(deps, env, info, title, description, metadata, fund_address),
+            recipients,
+        } => handle_create_proposal(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            metadata,
+            fund_address,
+            recipients,
+        ),
+        HandleMsg::UpdateProposal {
+            id,
+            title,
+            description,
+            metadata,
+            fund_address,
+            recipients,
+        } => handle_update_proposal(
+            deps,
+            env,
+            info,
+            id,
+            title,
+            description,
+            metadata,
+            fund_address,
+            recipients,
+        ),
+        HandleMsg::WithdrawProposal { id } => handle_withdraw_proposal(deps, env, info, id),
         HandleMsg::VoteProposal { proposal_id } => {
             handle_vote_proposal(deps, env, info, proposal_id)
         }
+        HandleMsg::CommitVote { commitment } => handle_commit_vote(deps, env, info, commitment),
+        HandleMsg::RevealVote { proposal_id, salt } => {
+            handle_reveal_vote(deps, env, info, proposal_id, salt)
+        }
+        HandleMsg::DelegateFunds { curator } => handle_delegate_funds(deps, env, info, curator),
+        HandleMsg::CuratorAllocate {
+            delegator,
+            allocations,
+        } => handle_curator_allocate(deps, env, info, delegator, allocations),
+        HandleMsg::RevokeDelegation { curator } => {
+            handle_revoke_delegation(deps, env, info, curator)
+        }
+        HandleMsg::DepositFunds { voter } => handle_deposit_funds(deps, env, info, voter),
+        HandleMsg::RelayedVote {
+            voter,
+            pubkey,
+            payload,
+            signature,
+        } => handle_relayed_vote(deps, env, info, voter, pubkey, payload, signature),
+        HandleMsg::SetProposalStatus { id, status } => {
+            handle_set_proposal_status(deps, env, info, id, status)
+        }
         HandleMsg::TriggerDistribution { .. } => handle_trigger_distribution(deps, env, info),
+        HandleMsg::UpdateConfig {
+            leftover_addr,
+            rollover_leftover,
+            voting_period,
+            proposal_period,
+            algorithm,
+            oracle,
+        } => handle_update_config(
+            deps,
+            env,
+            info,
+            leftover_addr,
+            rollover_leftover,
+            voting_period,
+            proposal_period,
+            algorithm,
+            oracle,
+        ),
+        HandleMsg::ProposeNewAdmin { addr } => handle_propose_new_admin(deps, env, info, addr),
+        HandleMsg::AcceptAdmin {} => handle_accept_admin(deps, env, info),
+        HandleMsg::RenounceAdmin {} => handle_renounce_admin(deps, env, info),
+        HandleMsg::AddToWhitelist { whitelist, addrs } => {
+            handle_add_to_whitelist(deps, env, info, whitelist, addrs)
+        }
+        HandleMsg::RemoveFromWhitelist { whitelist, addrs } => {
+            handle_remove_from_whitelist(deps, env, info, whitelist, addrs)
+        }
+        HandleMsg::SetVoterScores { scores } => handle_set_voter_scores(deps, env, info, scores),
+        HandleMsg::SetDenomRates { rates } => handle_set_denom_rates(deps, env, info, rates),
+        HandleMsg::RemoveDenoms { denoms } => handle_remove_denoms(deps, env, info, denoms),
+        HandleMsg::StartNextRound {
+            voting_period,
+            proposal_period,
+            reveal_period,
+        } => handle_start_next_round(
+            deps,
+            env,
+            info,
+            voting_period,
+            proposal_period,
+            reveal_period,
+        ),
+        HandleMsg::Pause {} => handle_pause(deps, env, info),
+        HandleMsg::Unpause {} => handle_unpause(deps, env, info),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_create_proposal(
     deps: DepsMut,
     env: Env,
@@ -81,14 +255,18 @@ pub fn handle_create_proposal(
     description: String,
     metadata: Option<Binary>,
     fund_address: HumanAddr,
+    recipients: Option<Vec<RecipientShare>>,
 ) -> Result<HandleResponse, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
     // check whitelist
-    if let Some(wl) = config.create_proposal_whitelist {
-        if !wl.contains(&deps.api.canonical_address(&info.sender)?) {
-            return Err(ContractError::Unauthorized {});
-        }
+    if !is_whitelisted(
+        deps.as_ref(),
+        &config,
+        Whitelist::CreateProposal,
+        &info.sender,
+    )? {
+        return Err(ContractError::Unauthorized {});
     }
 
     // check proposal expiration
@@ -96,6 +274,38 @@ pub fn handle_create_proposal(
         return Err(ContractError::ProposalPeriodExpired {});
     }
 
+    // validate proposal fields
+    validate_proposal_fields(
+        &config,
+        &env,
+        &title,
+        &description,
+        &metadata,
+        &fund_address,
+    )?;
+    let recipients = match recipients {
+        Some(recipients) => {
+            validate_recipients(&env, &recipients, config.max_recipients)?;
+            canonical_recipients(deps.as_ref(), recipients)?
+        }
+        None => vec![],
+    };
+
+    // collect proposal deposit
+    let deposit = match &config.proposal_deposit {
+        Some(expected) => {
+            let sent = extract_budget_coin(&info.sent_funds, &expected.denom)?;
+            if sent.amount != expected.amount {
+                return Err(ContractError::WrongProposalDeposit {
+                    expected: format!("{}{}", expected.amount, expected.denom),
+                    got: format!("{}{}", sent.amount, sent.denom),
+                });
+            }
+            Some(sent)
+        }
+        None => None,
+    };
+
     let id = nextval(&mut proposal_seq(deps.storage))?;
     let p = Proposal {
         id,
@@ -103,6 +313,9 @@ pub fn handle_create_proposal(
         description,
         metadata,
         fund_address: deps.api.canonical_address(&fund_address)?,
+        proposer: deps.api.canonical_address(&info.sender)?,
+        deposit,
+        recipients,
         ..Default::default()
     };
     PROPOSALS.save(deps.storage, id.into(), &p)?;
@@ -111,8 +324,10 @@ pub fn handle_create_proposal(
         messages: vec![],
         attributes: vec![
             attr("action", "create_proposal"),
-            attr("title", title),
+            attr("sender", &info.sender),
             attr("proposal_id", id),
+            attr("title", title),
+            attr("fund_address", fund_address),
         ],
         data: Some(Binary::from(id.to_be_bytes())),
     };
@@ -120,59 +335,191 @@ pub fn handle_create_proposal(
     Ok(res)
 }
 
-pub fn handle_vote_proposal(
+#[allow(clippy::too_many_arguments)]
+pub fn handle_update_proposal(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    proposal_id: u64,
+    id: u64,
+    title: Option<String>,
+    description: Option<String>,
+    metadata: Option<Binary>,
+    fund_address: Option<HumanAddr>,
+    recipients: Option<Vec<RecipientShare>>,
 ) -> Result<HandleResponse, ContractError> {
     let config = CONFIG.load(deps.storage)?;
+    let mut proposal = load_editable_proposal(deps.as_ref(), &env, &info, &config, id)?;
 
-    // check whitelist
-    if let Some(wl) = config.vote_proposal_whitelist {
-        if !wl.contains(&deps.api.canonical_address(&info.sender)?) {
-            return Err(ContractError::Unauthorized {});
+    let title = title.unwrap_or(proposal.title);
+    let description = description.unwrap_or(proposal.description);
+    let metadata = metadata.or(proposal.metadata);
+    let fund_address = match fund_address {
+        Some(addr) => addr,
+        None => deps.api.human_address(&proposal.fund_address)?,
+    };
+
+    // validate proposal fields
+    validate_proposal_fields(
+        &config,
+        &env,
+        &title,
+        &description,
+        &metadata,
+        &fund_address,
+    )?;
+    let recipients = match recipients {
+        Some(recipients) if recipients.is_empty() => vec![],
+        Some(recipients) => {
+            validate_recipients(&env, &recipients, config.max_recipients)?;
+            canonical_recipients(deps.as_ref(), recipients)?
         }
+        None => proposal.recipients,
+    };
+
+    proposal.title = title;
+    proposal.description = description;
+    proposal.metadata = metadata;
+    proposal.fund_address = deps.api.canonical_address(&fund_address)?;
+    proposal.recipients = recipients;
+    PROPOSALS.save(deps.storage, id.into(), &proposal)?;
+
+    let res = HandleResponse {
+        attributes: vec![
+            attr("action", "update_proposal"),
+            attr("sender", &info.sender),
+            attr("proposal_id", id),
+            attr("title", proposal.title),
+            attr("fund_address", fund_address),
+        ],
+        ..Default::default()
+    };
+
+    Ok(res)
+}
+
+pub fn handle_withdraw_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let proposal = load_editable_proposal(deps.as_ref(), &env, &info, &config, id)?;
+
+    PROPOSALS.remove(deps.storage, id.into());
+
+    // return deposit to proposer
+    let mut msgs = vec![];
+    if let Some(deposit) = proposal.deposit {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: info.sender.clone(),
+            amount: vec![deposit],
+        }));
     }
 
-    // check voting expiration
-    if config.voting_period.is_expired(&env.block) {
-        return Err(ContractError::VotingPeriodExpired {});
+    let res = HandleResponse {
+        messages: msgs,
+        attributes: vec![
+            attr("action", "withdraw_proposal"),
+            attr("sender", &info.sender),
+            attr("proposal_id", id),
+        ],
+        ..Default::default()
+    };
+
+    Ok(res)
+}
+
+fn canonical_recipients(deps: Deps, recipients: Vec<RecipientShare>) -> StdResult<Vec<Recipient>> {
+    recipients
+        .into_iter()
+        .map(|r| {
+            Ok(Recipient {
+                addr: deps.api.canonical_address(&r.addr)?,
+                share: r.share,
+            })
+        })
+        .collect()
+}
+
+// loads proposal if sender is the proposer and proposal is still editable
+fn load_editable_proposal(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    config: &Config,
+    id: u64,
+) -> Result<Proposal, ContractError> {
+    let proposal = PROPOSALS
+        .may_load(deps.storage, id.into())?
+        .ok_or(ContractError::ProposalNotFound {})?;
+
+    // only proposer can edit proposal
+    if deps.api.canonical_address(&info.sender)? != proposal.proposer {
+        return Err(ContractError::Unauthorized {});
     }
 
-    // validate sent funds and funding denom matches
-    let fund = extract_budget_coin(&info.sent_funds, &config.budget.denom)?;
+    // rejected proposals can't be withdrawn to get the deposit back
+    if proposal.status == ProposalStatus::Rejected {
+        return Err(ContractError::ProposalRejected {});
+    }
 
-    // check existence of the proposal and collect funds in proposal
-    let proposal = PROPOSALS.update(deps.storage, proposal_id.into(), |op| match op {
-        None => Err(ContractError::ProposalNotFound {}),
-        Some(mut proposal) => {
-            proposal.collected_funds += fund.amount;
-            Ok(proposal)
-        }
-    })?;
+    // check proposal expiration
+    if config.proposal_period.is_expired(&env.block) {
+        return Err(ContractError::ProposalPeriodExpired {});
+    }
+
+    // proposal is locked once it received a vote, committed votes may be for any proposal
+    let has_votes = VOTES
+        .prefix(id.into())
+        .range(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if has_votes || any_commitments(deps)? {
+        return Err(ContractError::ProposalHasVotes {});
+    }
+
+    Ok(proposal)
+}
+
+pub fn handle_vote_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // votes are hidden until revealed in commit reveal rounds
+    if config.commit_reveal.is_some() {
+        return Err(ContractError::CommitRevealEnabled {});
+    }
 
+    check_can_vote(deps.as_ref(), &env, &config, &info.sender)?;
+    let fund = extract_coin(&info.sent_funds)?;
+    let value = contribution(deps.as_ref(), &config, &fund)?;
+
+    let voter = deps.api.canonical_address(&info.sender)?;
     let vote = Vote {
         proposal_id,
-        voter: deps.api.canonical_address(&info.sender)?,
+        weight: cw4_weight(deps.as_ref(), &config, &voter)?,
+        voter,
         fund,
+        value: Some(value),
+        curator: None,
     };
-
-    // check sender did not voted on proposal
-    let vote_key = VOTES.key((proposal_id.into(), info.sender.as_bytes()));
-    if vote_key.may_load(deps.storage)?.is_some() {
-        return Err(ContractError::AddressAlreadyVotedProject {});
-    }
-
-    // save vote
-    vote_key.save(deps.storage, &vote)?;
+    let proposal = cast_vote(deps.storage, &config, &vote)?;
 
     let res = HandleResponse {
         attributes: vec![
             attr("action", "vote_proposal"),
-            attr("proposal_key", proposal_id),
-            attr("voter", deps.api.human_address(&vote.voter)?),
-            attr("collected_fund", proposal.collected_funds),
+            attr("sender", &info.sender),
+            attr("proposal_id", proposal_id),
+            attr("amount", vote.fund.amount),
+            attr("denom", &vote.fund.denom),
+            attr("value", value),
+            attr("collected_funds", proposal.collected_funds),
         ],
         ..Default::default()
     };
@@ -180,262 +527,4361 @@ pub fn handle_vote_proposal(
     Ok(res)
 }
 
-pub fn handle_trigger_distribution(
+pub fn handle_commit_vote(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    commitment: Binary,
 ) -> Result<HandleResponse, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
-    // only admin can trigger distribution
-    if deps.api.canonical_address(&info.sender)? != config.admin {
-        return Err(ContractError::Unauthorized {});
+    if config.commit_reveal.is_none() {
+        return Err(ContractError::CommitRevealDisabled {});
     }
 
-    // check voting period expiration
+    check_can_vote(deps.as_ref(), &env, &config, &info.sender)?;
+    let fund = extract_coin(&info.sent_funds)?;
+    let value = contribution(deps.as_ref(), &config, &fund)?;
+
+    // funds are escrowed until the vote is revealed
+    let voter = deps.api.canonical_address(&info.sender)?;
+    let key = COMMITMENTS.key((voter.as_slice(), commitment.as_slice()));
+    if key.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::DuplicateCommitment {});
+    }
+    key.save(
+        deps.storage,
+        &Commitment {
+            voter,
+            commitment: commitment.clone(),
+            fund: fund.clone(),
+            value,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "commit_vote"),
+            attr("sender", &info.sender),
+            attr("commitment", commitment),
+            attr("amount", fund.amount),
+            attr("denom", fund.denom),
+            attr("value", value),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_reveal_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    salt: Binary,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let commit_reveal = match &config.commit_reveal {
+        Some(commit_reveal) => commit_reveal,
+        None => return Err(ContractError::CommitRevealDisabled {}),
+    };
+
+    // votes are revealed once voting is closed
     if !config.voting_period.is_expired(&env.block) {
         return Err(ContractError::VotingPeriodNotExpired {});
     }
+    if commit_reveal.reveal_period.is_expired(&env.block) {
+        return Err(ContractError::RevealPeriodExpired {});
+    }
 
-    let query_proposals: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, None, None, Order::Ascending)
-        .collect();
+    let voter = deps.api.canonical_address(&info.sender)?;
+    let hash = commitment_hash(&info.sender, proposal_id, &salt);
+    let key = COMMITMENTS.key((voter.as_slice(), hash.as_slice()));
+    let commitment = key
+        .may_load(deps.storage)?
+        .ok_or(ContractError::CommitmentNotFound {})?;
+    key.remove(deps.storage);
 
-    let proposals: Vec<Proposal> = query_proposals?.into_iter().map(|p| p.1).collect();
+    // votes on rejected or withdrawn proposals are refunded
+    match PROPOSALS.may_load(deps.storage, proposal_id.into())? {
+        Some(proposal) if proposal.status != ProposalStatus::Rejected => {}
+        _ => {
+            return Ok(HandleResponse {
+                messages: vec![CosmosMsg::Bank(BankMsg::Send {
+                    from_address: env.contract.address,
+                    to_address: info.sender.clone(),
+                    amount: vec![commitment.fund.clone()],
+                })],
+                attributes: vec![
+                    attr("action", "reveal_vote"),
+                    attr("sender", &info.sender),
+                    attr("proposal_id", proposal_id),
+                    attr("refunded", commitment.fund.amount),
+                ],
+                ..Default::default()
+            });
+        }
+    }
 
-    let mut grants: Vec<RawGrant> = vec![];
-    // collect proposals under grants
-    for p in proposals {
-        let vote_query: StdResult<Vec<(Vec<u8>, Vote)>> = VOTES
-            .prefix(p.id.into())
-            .range(deps.storage, None, None, Order::Ascending)
-            .collect();
+    let vote = Vote {
+        proposal_id,
+        weight: cw4_weight(deps.as_ref(), &config, &voter)?,
+        voter,
+        fund: commitment.fund,
+        value: Some(commitment.value),
+        curator: None,
+    };
+    let proposal = cast_vote(deps.storage, &config, &vote)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "reveal_vote"),
+            attr("sender", &info.sender),
+            attr("proposal_id", proposal_id),
+            attr("amount", vote.fund.amount),
+            attr("denom", &vote.fund.denom),
+            attr("value", commitment.value),
+            attr("collected_funds", proposal.collected_funds),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_delegate_funds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curator: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // allocations are public votes
+    if config.commit_reveal.is_some() {
+        return Err(ContractError::CommitRevealEnabled {});
+    }
+
+    // votes are attributed to the delegator
+    check_can_vote(deps.as_ref(), &env, &config, &info.sender)?;
+    let fund = extract_coin(&info.sent_funds)?;
+    contribution_value(deps.as_ref(), &config, &fund)?;
+
+    let delegator = deps.api.canonical_address(&info.sender)?;
+    let curator_addr = deps.api.canonical_address(&curator)?;
+    let delegation = DELEGATIONS.update(
+        deps.storage,
+        (curator_addr.as_slice(), delegator.as_slice()),
+        |d| match d {
+            // delegations are topped up in the same denom
+            Some(mut d) if d.fund.denom == fund.denom => {
+                d.fund.amount += fund.amount;
+                Ok(d)
+            }
+            Some(d) => Err(ContractError::WrongFundCoin {
+                expected: d.fund.denom,
+                got: fund.denom.clone(),
+            }),
+            None => Ok(Delegation {
+                delegator: delegator.clone(),
+                curator: curator_addr.clone(),
+                fund: fund.clone(),
+            }),
+        },
+    )?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "delegate_funds"),
+            attr("sender", &info.sender),
+            attr("curator", curator),
+            attr("amount", fund.amount),
+            attr("denom", fund.denom),
+            attr("delegated", delegation.fund.amount),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_curator_allocate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delegator: HumanAddr,
+    allocations: Vec<Allocation>,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    check_can_vote(deps.as_ref(), &env, &config, &delegator)?;
+
+    let curator = deps.api.canonical_address(&info.sender)?;
+    let delegator_addr = deps.api.canonical_address(&delegator)?;
+    let key = DELEGATIONS.key((curator.as_slice(), delegator_addr.as_slice()));
+    let mut delegation = key
+        .may_load(deps.storage)?
+        .ok_or(ContractError::DelegationNotFound {})?;
 
-        let mut votes: Vec<u128> = vec![];
-        for v in vote_query? {
-            votes.push(v.1.fund.amount.u128());
+    let mut requested: u128 = 0;
+    for a in allocations.iter() {
+        if a.amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
         }
-        let grant = RawGrant {
-            addr: p.fund_address,
-            funds: votes,
-            collected_vote_funds: p.collected_funds.u128(),
+        requested = requested
+            .checked_add(a.amount.u128())
+            .ok_or(ContractError::Overflow {})?;
+    }
+    if requested > delegation.fund.amount.u128() {
+        return Err(ContractError::InsufficientDelegatedFunds {
+            available: delegation.fund.amount.u128(),
+            requested,
+        });
+    }
+
+    let mut attributes = vec![
+        attr("action", "curator_allocate"),
+        attr("sender", &info.sender),
+        attr("delegator", &delegator),
+    ];
+    let weight = cw4_weight(deps.as_ref(), &config, &delegator_addr)?;
+    for a in allocations {
+        let fund = coin(a.amount.u128(), &delegation.fund.denom);
+        let value = contribution(deps.as_ref(), &config, &fund)?;
+        let vote = Vote {
+            proposal_id: a.proposal_id,
+            voter: delegator_addr.clone(),
+            fund,
+            value: Some(value),
+            curator: Some(curator.clone()),
+            weight,
         };
+        cast_vote(deps.storage, &config, &vote)?;
+        attributes.push(attr("proposal_id", a.proposal_id));
+        attributes.push(attr("amount", a.amount));
+    }
 
-        grants.push(grant);
+    // fully allocated delegations are removed
+    delegation.fund.amount = Uint128(delegation.fund.amount.u128() - requested);
+    if delegation.fund.amount.is_zero() {
+        key.remove(deps.storage);
+    } else {
+        key.save(deps.storage, &delegation)?;
     }
+    attributes.push(attr("remaining", delegation.fund.amount));
 
-    let (distr_funds, leftover) = match config.algorithm {
-        QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism { .. } => {
-            calculate_clr(grants, Some(config.budget.amount.u128()))?
-        }
-    };
+    Ok(HandleResponse {
+        attributes,
+        ..Default::default()
+    })
+}
 
-    let mut msgs = vec![];
-    for f in distr_funds {
-        msgs.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address.clone(),
-            to_address: deps.api.human_address(&f.addr)?,
-            amount: vec![coin(f.grant + f.collected_vote_funds, &config.budget.denom)],
-        }));
+pub fn handle_revoke_delegation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    curator: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // unallocated funds are refunded on distribution once voting is closed
+    if config.voting_period.is_expired(&env.block) {
+        return Err(ContractError::VotingPeriodExpired {});
     }
 
-    let leftover_msg: CosmosMsg = CosmosMsg::Bank(BankMsg::Send {
-        from_address: env.contract.address,
-        to_address: deps.api.human_address(&config.leftover_addr)?,
-        amount: vec![coin(leftover, config.budget.denom)],
-    });
+    let delegator = deps.api.canonical_address(&info.sender)?;
+    let curator_addr = deps.api.canonical_address(&curator)?;
+    let key = DELEGATIONS.key((curator_addr.as_slice(), delegator.as_slice()));
+    let delegation = key
+        .may_load(deps.storage)?
+        .ok_or(ContractError::DelegationNotFound {})?;
+    key.remove(deps.storage);
 
-    msgs.push(leftover_msg);
-    let res = HandleResponse {
-        messages: msgs,
-        attributes: vec![attr("action", "trigger_distribution")],
-        data: None,
-    };
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: info.sender.clone(),
+            amount: vec![delegation.fund.clone()],
+        })],
+        attributes: vec![
+            attr("action", "revoke_delegation"),
+            attr("sender", &info.sender),
+            attr("curator", curator),
+            attr("refunded", delegation.fund.amount),
+        ],
+        ..Default::default()
+    })
+}
 
-    Ok(res)
+pub fn handle_deposit_funds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    voter: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // relayed votes are public
+    if config.commit_reveal.is_some() {
+        return Err(ContractError::CommitRevealEnabled {});
+    }
+    if config.voting_period.is_expired(&env.block) {
+        return Err(ContractError::VotingPeriodExpired {});
+    }
+
+    let fund = extract_coin(&info.sent_funds)?;
+    contribution_value(deps.as_ref(), &config, &fund)?;
+
+    // deposits are topped up in the same denom
+    let voter_addr = deps.api.canonical_address(&voter)?;
+    let deposit = DEPOSITS.update(deps.storage, voter_addr.as_slice(), |d| match d {
+        Some(mut d) if d.denom == fund.denom => {
+            d.amount += fund.amount;
+            Ok(d)
+        }
+        Some(d) => Err(ContractError::WrongFundCoin {
+            expected: d.denom,
+            got: fund.denom.clone(),
+        }),
+        None => Ok(fund.clone()),
+    })?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "deposit_funds"),
+            attr("sender", &info.sender),
+            attr("voter", voter),
+            attr("amount", fund.amount),
+            attr("denom", fund.denom),
+            attr("deposit", deposit.amount),
+        ],
+        ..Default::default()
+    })
 }
 
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::ProposalByID { id } => to_binary(&query_proposal_id(deps, id)?),
-        QueryMsg::AllProposals {} => to_binary(&query_all_proposals(deps)?),
+#[allow(clippy::too_many_arguments)]
+pub fn handle_relayed_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    voter: HumanAddr,
+    pubkey: Binary,
+    payload: Binary,
+    signature: Binary,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if config.commit_reveal.is_some() {
+        return Err(ContractError::CommitRevealEnabled {});
+    }
+
+    // check payload is signed by voter for this contract
+    let signer = verify_signature(&payload, &signature, &pubkey)?;
+    let voter_addr = deps.api.canonical_address(&voter)?;
+    if signer != voter_addr {
+        return Err(ContractError::PubkeyMismatch {});
+    }
+    let payload: RelayedVotePayload = from_slice(&payload)?;
+    if payload.contract != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if payload.expiry.is_expired(&env.block) {
+        return Err(ContractError::SignatureExpired {});
+    }
+
+    // nonces are used in order, so each signed vote is relayed once
+    let nonce = RELAY_NONCES
+        .may_load(deps.storage, voter_addr.as_slice())?
+        .unwrap_or_default();
+    if payload.nonce != nonce {
+        return Err(ContractError::InvalidNonce {
+            expected: nonce,
+            got: payload.nonce,
+        });
+    }
+    check_can_vote(deps.as_ref(), &env, &config, &voter)?;
+    if payload.amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    // vote is paid from voter's deposit
+    let mut deposit = DEPOSITS
+        .may_load(deps.storage, voter_addr.as_slice())?
+        .unwrap_or_else(|| coin(0, &config.budget.denom));
+    if payload.amount > deposit.amount {
+        return Err(ContractError::InsufficientDeposit {
+            available: deposit.amount.u128(),
+            requested: payload.amount.u128(),
+        });
+    }
+    let fund = coin(payload.amount.u128(), &deposit.denom);
+    let value = contribution(deps.as_ref(), &config, &fund)?;
+    RELAY_NONCES.save(deps.storage, voter_addr.as_slice(), &(nonce + 1))?;
+    deposit.amount = Uint128(deposit.amount.u128() - payload.amount.u128());
+    if deposit.amount.is_zero() {
+        DEPOSITS.remove(deps.storage, voter_addr.as_slice());
+    } else {
+        DEPOSITS.save(deps.storage, voter_addr.as_slice(), &deposit)?;
+    }
+
+    let vote = Vote {
+        proposal_id: payload.proposal_id,
+        weight: cw4_weight(deps.as_ref(), &config, &voter_addr)?,
+        voter: voter_addr,
+        fund,
+        value: Some(value),
+        curator: None,
+    };
+    let proposal = cast_vote(deps.storage, &config, &vote)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "relayed_vote"),
+            attr("sender", &info.sender),
+            attr("voter", voter),
+            attr("nonce", nonce),
+            attr("proposal_id", payload.proposal_id),
+            attr("amount", vote.fund.amount),
+            attr("denom", &vote.fund.denom),
+            attr("value", value),
+            attr("collected_funds", proposal.collected_funds),
+        ],
+        ..Default::default()
+    })
+}
+
+// checks voter is whitelisted and voting is open
+fn check_can_vote(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    voter: &HumanAddr,
+) -> Result<(), ContractError> {
+    // check whitelist
+    if !is_whitelisted(deps, config, Whitelist::VoteProposal, voter)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // check voting expiration
+    if config.voting_period.is_expired(&env.block) {
+        return Err(ContractError::VotingPeriodExpired {});
+    }
+
+    Ok(())
+}
+
+// values a contribution and checks it is over min contribution
+fn contribution(deps: Deps, config: &Config, fund: &Coin) -> Result<Uint128, ContractError> {
+    let value = contribution_value(deps, config, fund)?;
+    if value < config.min_contribution {
+        return Err(ContractError::ContributionTooLow {
+            min: config.min_contribution.u128(),
+            got: value.u128(),
+        });
+    }
+    Ok(value)
+}
+
+// collects vote funds in proposal and saves the vote
+fn cast_vote(
+    storage: &mut dyn Storage,
+    config: &Config,
+    vote: &Vote,
+) -> Result<Proposal, ContractError> {
+    let value = vote.budget_value();
+
+    // check existence of the proposal and collect funds in proposal
+    let proposal = PROPOSALS.update(storage, vote.proposal_id.into(), |op| match op {
+        None => Err(ContractError::ProposalNotFound {}),
+        Some(proposal) if proposal.status == ProposalStatus::Rejected => {
+            Err(ContractError::ProposalRejected {})
+        }
+        Some(mut proposal) => {
+            proposal.collected_funds += value;
+            proposal.matched_funds += match config.max_matched_contribution {
+                Some(max) => value.min(max),
+                None => value,
+            };
+            Ok(proposal)
+        }
+    })?;
+
+    // check sender did not voted on proposal
+    let vote_key = VOTES.key((vote.proposal_id.into(), vote.voter.as_slice()));
+    if vote_key.may_load(storage)?.is_some() {
+        return Err(ContractError::AddressAlreadyVotedProject {});
+    }
+
+    // save vote and index it by voter
+    vote_key.save(storage, vote)?;
+    VOTER_VOTES.save(
+        storage,
+        (vote.voter.as_slice(), vote.proposal_id.into()),
+        &Empty {},
+    )?;
+
+    Ok(proposal)
+}
+
+// converts a contribution into budget units with the oracle price or the admin set rate
+fn contribution_value(deps: Deps, config: &Config, fund: &Coin) -> Result<Uint128, ContractError> {
+    if fund.denom == config.budget.denom {
+        return Ok(fund.amount);
+    }
+    if let Some(oracle) = &config.oracle {
+        let price: PriceResponse = deps.querier.query_wasm_smart(
+            deps.api.human_address(oracle)?,
+            &OracleQueryMsg::Price {
+                base_denom: fund.denom.clone(),
+                quote_denom: config.budget.denom.clone(),
+            },
+        )?;
+        if price.rate.is_zero() {
+            return Err(ContractError::DenomNotAccepted {
+                denom: fund.denom.clone(),
+            });
+        }
+        return Ok(fund.amount * price.rate);
+    }
+    match DENOM_RATES.may_load(deps.storage, fund.denom.as_bytes())? {
+        Some(rate) => Ok(fund.amount * rate),
+        None => Err(ContractError::DenomNotAccepted {
+            denom: fund.denom.clone(),
+        }),
+    }
+}
+
+pub fn handle_set_proposal_status(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+    status: ProposalStatus,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only admin can moderate proposals
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    PROPOSALS.update(deps.storage, id.into(), |op| match op {
+        None => Err(ContractError::ProposalNotFound {}),
+        Some(mut proposal) => {
+            proposal.status = status;
+            Ok(proposal)
+        }
+    })?;
+
+    let res = HandleResponse {
+        attributes: vec![
+            attr("action", "set_proposal_status"),
+            attr("sender", &info.sender),
+            attr("proposal_id", id),
+            attr("status", status),
+        ],
+        ..Default::default()
+    };
+
+    Ok(res)
+}
+
+pub fn handle_trigger_distribution(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only admin can trigger distribution, anyone can once admin is renounced
+    if config.admin.is_some() && !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // check voting period expiration
+    if !config.voting_period.is_expired(&env.block) {
+        return Err(ContractError::VotingPeriodNotExpired {});
+    }
+
+    // committed votes have to be revealed first
+    if let Some(commit_reveal) = &config.commit_reveal {
+        if !commit_reveal.reveal_period.is_expired(&env.block) {
+            return Err(ContractError::RevealPeriodNotExpired {});
+        }
+    }
+
+    // each round is distributed once
+    if config.distributed {
+        return Err(ContractError::AlreadyDistributed {});
+    }
+
+    let query_proposals: StdResult<Vec<_>> = PROPOSALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect();
+
+    let proposals: Vec<Proposal> = query_proposals?.into_iter().map(|p| p.1).collect();
+
+    let mut grants: Vec<RawGrant> = vec![];
+    // payout splits of each grant, in the same order as grants
+    let mut splits: Vec<Vec<Recipient>> = vec![];
+    // direct contributions of each grant by denom
+    let mut contributions: Vec<Vec<Coin>> = vec![];
+    let mut refunds: Vec<Vote> = vec![];
+    let mut deposit_msgs: Vec<CosmosMsg> = vec![];
+    // collect proposals under grants
+    for p in proposals {
+        // deposits are returned to proposers in good standing, slashed otherwise
+        if let Some(deposit) = p.deposit.clone().filter(|d| !d.amount.is_zero()) {
+            let to_address = match p.status {
+                ProposalStatus::Rejected => deps.api.human_address(&config.leftover_addr)?,
+                _ => deps.api.human_address(&p.proposer)?,
+            };
+            deposit_msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address,
+                amount: vec![deposit],
+            }));
+        }
+
+        let vote_query: StdResult<Vec<(Vec<u8>, Vote)>> = VOTES
+            .prefix(p.id.into())
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect();
+
+        // rejected proposals are skipped and their votes refunded
+        if p.status == ProposalStatus::Rejected {
+            refunds.extend(vote_query?.into_iter().map(|v| v.1));
+            continue;
+        }
+
+        let votes: Vec<Vote> = vote_query?.into_iter().map(|v| v.1).collect();
+        let mut direct = vec![];
+        for v in votes.iter() {
+            add_coin(&mut direct, &v.fund);
+        }
+        contributions.push(direct);
+        grants.push(raw_grant(deps.as_ref(), &config, &p, votes)?);
+        splits.push(p.recipients);
+    }
+
+    let (distr_funds, leftover) = match config.algorithm {
+        QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism { .. } => calculate_clr(
+            grants,
+            Some(config.budget.amount.u128()),
+            config.max_matched_contribution.map(|m| m.u128()),
+        )?,
+    };
+
+    // keep the result for auditing after payout
+    let result = DistributionResult {
+        round: config.round,
+        algorithm: config.algorithm.clone(),
+        grants: distr_funds.iter().cloned().map(Grant::from).collect(),
+        leftover: coin(leftover, &config.budget.denom),
+        height: env.block.height,
+    };
+    DISTRIBUTION_RESULTS.save(deps.storage, config.round.into(), &result)?;
+
+    let mut attributes = vec![
+        attr("action", "trigger_distribution"),
+        attr("sender", &info.sender),
+        attr("round", config.round),
+    ];
+    let total_grants: u128 = distr_funds.iter().map(|f| f.grant).sum();
+    let total_contributions: u128 = distr_funds.iter().map(|f| f.collected_vote_funds).sum();
+
+    let mut msgs = vec![];
+    for ((f, recipients), direct) in distr_funds.into_iter().zip(splits).zip(contributions) {
+        // one entry per grantee followed by its payouts
+        attributes.push(attr("proposal_id", f.proposal_id));
+        attributes.push(attr("grant", f.grant));
+        attributes.push(attr("contributions", f.collected_vote_funds));
+
+        // grant is paid in budget denom, direct contributions in the denom they were sent
+        let mut payout = vec![coin(f.grant, &config.budget.denom)];
+        for c in direct.iter() {
+            add_coin(&mut payout, c);
+        }
+        let recipients = if recipients.is_empty() {
+            vec![Recipient {
+                addr: f.addr,
+                share: BASIS_POINTS,
+            }]
+        } else {
+            recipients
+        };
+        let mut amounts: Vec<Vec<Coin>> = vec![vec![]; recipients.len()];
+        for c in payout.iter() {
+            let split = split_payout(c.amount.u128(), &recipients);
+            for (i, (_, amount)) in split.into_iter().enumerate() {
+                // zero coins are rejected by the bank module
+                if amount > 0 {
+                    amounts[i].push(coin(amount, &c.denom));
+                }
+            }
+        }
+        for (r, amount) in recipients.into_iter().zip(amounts) {
+            let recipient = deps.api.human_address(&r.addr)?;
+            let (budget_payout, other): (Vec<&Coin>, Vec<&Coin>) =
+                amount.iter().partition(|c| c.denom == config.budget.denom);
+            attributes.push(attr("recipient", &recipient));
+            attributes.push(attr(
+                "payout",
+                budget_payout.first().map_or(Uint128::zero(), |c| c.amount),
+            ));
+            if !other.is_empty() {
+                let other: Vec<String> = other
+                    .iter()
+                    .map(|c| format!("{}{}", c.amount, c.denom))
+                    .collect();
+                attributes.push(attr("direct_payout", other.join(",")));
+            }
+            // nothing to send to recipients of an empty payout
+            if amount.is_empty() {
+                continue;
+            }
+            msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: recipient,
+                amount,
+            }));
+        }
+    }
+    attributes.push(attr("total_grants", total_grants));
+    attributes.push(attr("total_contributions", total_contributions));
+    attributes.push(attr("refunded_votes", refunds.len()));
+    attributes.push(attr("leftover", leftover));
+
+    // zero coins are rejected by the bank module, so empty refunds are skipped
+    for r in refunds.into_iter().filter(|r| !r.fund.amount.is_zero()) {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: deps.api.human_address(&r.voter)?,
+            amount: vec![r.fund],
+        }));
+    }
+    msgs.extend(deposit_msgs);
+
+    // unrevealed commitments are refunded or sent to leftover_addr
+    if let Some(commit_reveal) = &config.commit_reveal {
+        let commitments: StdResult<Vec<_>> = COMMITMENTS
+            .sub_prefix(())
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect();
+        let commitments: Vec<Commitment> = commitments?.into_iter().map(|c| c.1).collect();
+        attributes.push(attr("unrevealed_votes", commitments.len()));
+        let mut forfeited = vec![];
+        for c in commitments {
+            COMMITMENTS.remove(deps.storage, (c.voter.as_slice(), c.commitment.as_slice()));
+            if c.fund.amount.is_zero() {
+                continue;
+            }
+            if commit_reveal.refund_unrevealed {
+                msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                    from_address: env.contract.address.clone(),
+                    to_address: deps.api.human_address(&c.voter)?,
+                    amount: vec![c.fund],
+                }));
+            } else {
+                add_coin(&mut forfeited, &c.fund);
+            }
+        }
+        if !forfeited.is_empty() {
+            msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: deps.api.human_address(&config.leftover_addr)?,
+                amount: forfeited,
+            }));
+        }
+    }
+
+    // unallocated delegated funds are refunded
+    let delegations: StdResult<Vec<_>> = DELEGATIONS
+        .sub_prefix(())
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect();
+    let delegations: Vec<Delegation> = delegations?.into_iter().map(|d| d.1).collect();
+    if !delegations.is_empty() {
+        attributes.push(attr("refunded_delegations", delegations.len()));
+    }
+    for d in delegations {
+        DELEGATIONS.remove(deps.storage, (d.curator.as_slice(), d.delegator.as_slice()));
+        if d.fund.amount.is_zero() {
+            continue;
+        }
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: deps.api.human_address(&d.delegator)?,
+            amount: vec![d.fund],
+        }));
+    }
+
+    // unused deposits are refunded to voters
+    let deposits: StdResult<Vec<_>> = DEPOSITS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect();
+    let deposits = deposits?;
+    if !deposits.is_empty() {
+        attributes.push(attr("refunded_deposits", deposits.len()));
+    }
+    for (voter, deposit) in deposits {
+        DEPOSITS.remove(deps.storage, &voter);
+        if deposit.amount.is_zero() {
+            continue;
+        }
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: deps.api.human_address(&CanonicalAddr::from(voter))?,
+            amount: vec![deposit],
+        }));
+    }
+
+    // leftover is either kept for the next round or sent to leftover_addr
+    if config.rollover_leftover {
+        let rollover = Rollover {
+            round: config.round,
+            amount: coin(leftover, &config.budget.denom),
+            height: env.block.height,
+        };
+        ROLLOVERS.save(deps.storage, config.round.into(), &rollover)?;
+        attributes.push(attr("rolled_over", leftover));
+    } else if leftover > 0 {
+        let leftover_msg: CosmosMsg = CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: deps.api.human_address(&config.leftover_addr)?,
+            amount: vec![coin(leftover, &config.budget.denom)],
+        });
+        msgs.push(leftover_msg);
+    }
+
+    config.distributed = true;
+    CONFIG.save(deps.storage, &config)?;
+
+    let res = HandleResponse {
+        messages: msgs,
+        attributes,
+        data: None,
+    };
+
+    Ok(res)
+}
+
+// weighs proposal votes for matching
+fn raw_grant(
+    deps: Deps,
+    config: &Config,
+    proposal: &Proposal,
+    votes: Vec<Vote>,
+) -> StdResult<RawGrant> {
+    let mut funds: Vec<Contribution> = vec![];
+    for v in votes {
+        funds.push(Contribution {
+            weight: voter_weight(deps, config, &v)?,
+            amount: v.budget_value().u128(),
+            voter: v.voter,
+        });
+    }
+    // proposals under the contributor threshold are left out of matching
+    if (funds.len() as u64) < config.min_unique_contributors {
+        funds.clear();
+    }
+
+    Ok(RawGrant {
+        proposal_id: proposal.id,
+        addr: proposal.fund_address.clone(),
+        funds,
+        collected_vote_funds: proposal.collected_funds.u128(),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    leftover_addr: Option<HumanAddr>,
+    rollover_leftover: Option<bool>,
+    voting_period: Option<Expiration>,
+    proposal_period: Option<Expiration>,
+    algorithm: Option<QuadraticFundingAlgorithm>,
+    oracle: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only admin can update config
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut attributes = vec![
+        attr("action", "update_config"),
+        attr("sender", &info.sender),
+    ];
+
+    if let Some(leftover_addr) = leftover_addr {
+        config.leftover_addr = deps.api.canonical_address(&leftover_addr)?;
+        attributes.push(attr("leftover_addr", leftover_addr));
+    }
+
+    if let Some(rollover_leftover) = rollover_leftover {
+        config.rollover_leftover = rollover_leftover;
+        attributes.push(attr("rollover_leftover", rollover_leftover));
+    }
+
+    // periods can be changed while open, but never set to the past
+    if let Some(proposal_period) = proposal_period {
+        if config.proposal_period.is_expired(&env.block) || proposal_period.is_expired(&env.block) {
+            return Err(ContractError::ProposalPeriodExpired {});
+        }
+        config.proposal_period = proposal_period;
+        attributes.push(attr("proposal_period", proposal_period));
+    }
+    if let Some(voting_period) = voting_period {
+        if config.voting_period.is_expired(&env.block) || voting_period.is_expired(&env.block) {
+            return Err(ContractError::VotingPeriodExpired {});
+        }
+        // votes must still be revealable after voting
+        if let Some(commit_reveal) = &config.commit_reveal {
+            validate_reveal_period(&commit_reveal.reveal_period, &voting_period)?;
+        }
+        config.voting_period = voting_period;
+        attributes.push(attr("voting_period", voting_period));
+    }
+
+    // algorithm can't be changed once votes are cast
+    if let Some(algorithm) = algorithm {
+        if any_votes(deps.as_ref())? {
+            return Err(ContractError::VotingStarted {});
+        }
+        config.algorithm = algorithm;
+        attributes.push(attr("algorithm", "updated"));
+    }
+
+    // votes keep the value they were cast with
+    if let Some(oracle) = oracle {
+        config.oracle = Some(deps.api.canonical_address(&oracle)?);
+        attributes.push(attr("oracle", oracle));
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes,
+        ..Default::default()
+    })
+}
+
+pub fn handle_propose_new_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    addr: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only admin can propose new admin
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.pending_admin = Some(deps.api.canonical_address(&addr)?);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "propose_new_admin"),
+            attr("sender", &info.sender),
+            attr("pending_admin", addr),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_accept_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only pending admin can accept
+    let sender = deps.api.canonical_address(&info.sender)?;
+    match &config.pending_admin {
+        None => return Err(ContractError::NoPendingAdmin {}),
+        Some(pending) if *pending != sender => return Err(ContractError::Unauthorized {}),
+        Some(_) => {}
+    }
+
+    config.admin = config.pending_admin.take();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes: vec![attr("action", "accept_admin"), attr("sender", &info.sender)],
+        ..Default::default()
+    })
+}
+
+pub fn handle_renounce_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only admin can renounce
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // rolled over leftover is only spent by the admin starting the next round
+    if config.rollover_leftover
+        || ROLLOVERS
+            .may_load(deps.storage, config.round.into())?
+            .is_some()
+    {
+        return Err(ContractError::RolloverPending {});
+    }
+
+    config.admin = None;
+    config.pending_admin = None;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "renounce_admin"),
+            attr("sender", &info.sender),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_add_to_whitelist(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    whitelist: Whitelist,
+    addrs: Vec<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only admin can manage whitelists
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // members of a disabled or group backed whitelist would never be checked
+    if whitelist.group(&config).is_some() {
+        return Err(ContractError::WhitelistAndGroupConflict {});
+    }
+    if !whitelist.is_enabled(&config) {
+        return Err(ContractError::WhitelistNotEnabled {});
+    }
+
+    for addr in addrs.iter() {
+        let addr = deps.api.canonical_address(addr)?;
+        whitelist
+            .members()
+            .save(deps.storage, addr.as_slice(), &Empty {})?;
+    }
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "add_to_whitelist"),
+            attr("sender", &info.sender),
+            attr("whitelist", whitelist),
+            attr("count", addrs.len()),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_remove_from_whitelist(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    whitelist: Whitelist,
+    addrs: Vec<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only admin can manage whitelists
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for addr in addrs.iter() {
+        let addr = deps.api.canonical_address(addr)?;
+        whitelist.members().remove(deps.storage, addr.as_slice());
+    }
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "remove_from_whitelist"),
+            attr("sender", &info.sender),
+            attr("whitelist", whitelist),
+            attr("count", addrs.len()),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_set_voter_scores(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    scores: Vec<VoterScore>,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only admin can set scores
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(s) = scores.iter().find(|s| s.score > MAX_VOTER_SCORE) {
+        return Err(ContractError::VoterScoreTooHigh {
+            max: MAX_VOTER_SCORE,
+            got: s.score,
+        });
+    }
+
+    for s in scores.iter() {
+        let addr = deps.api.canonical_address(&s.addr)?;
+        VOTER_SCORES.save(deps.storage, addr.as_slice(), &s.score)?;
+    }
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "set_voter_scores"),
+            attr("sender", &info.sender),
+            attr("count", scores.len()),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_set_denom_rates(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    rates: Vec<DenomRate>,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only admin can set rates
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    validate_denom_rates(&config.budget.denom, &rates)?;
+    for r in rates.iter() {
+        DENOM_RATES.save(deps.storage, r.denom.as_bytes(), &r.rate)?;
+    }
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "set_denom_rates"),
+            attr("sender", &info.sender),
+            attr("count", rates.len()),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_remove_denoms(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denoms: Vec<String>,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only admin can remove denoms
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for d in denoms.iter() {
+        DENOM_RATES.remove(deps.storage, d.as_bytes());
+    }
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "remove_denoms"),
+            attr("sender", &info.sender),
+            attr("count", denoms.len()),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_start_next_round(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    voting_period: Expiration,
+    proposal_period: Expiration,
+    reveal_period: Option<Expiration>,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only admin can start next round
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // current round must be paid out first
+    if !config.distributed {
+        return Err(ContractError::RoundNotDistributed {});
+    }
+
+    if proposal_period.is_expired(&env.block) {
+        return Err(ContractError::ProposalPeriodExpired {});
+    }
+    if voting_period.is_expired(&env.block) {
+        return Err(ContractError::VotingPeriodExpired {});
+    }
+    if let Some(commit_reveal) = config.commit_reveal.as_mut() {
+        match reveal_period {
+            Some(p) if p.is_expired(&env.block) => {
+                return Err(ContractError::RevealPeriodExpired {})
+            }
+            Some(p) => {
+                validate_reveal_period(&p, &voting_period)?;
+                commit_reveal.reveal_period = p;
+            }
+            None => return Err(ContractError::RevealPeriodRequired {}),
+        }
+    }
+
+    // budget is topped up by sent funds
+    let top_up = if info.sent_funds.is_empty() {
+        Uint128::zero()
+    } else {
+        extract_budget_coin(&info.sent_funds, &config.budget.denom)?.amount
+    };
+    let rolled_over = ROLLOVERS
+        .may_load(deps.storage, config.round.into())?
+        .map_or(Uint128::zero(), |r| r.amount.amount);
+
+    clear_round(deps.storage)?;
+
+    config.round += 1;
+    config.distributed = false;
+    config.voting_period = voting_period;
+    config.proposal_period = proposal_period;
+    config.budget = coin(rolled_over.u128() + top_up.u128(), &config.budget.denom);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "start_next_round"),
+            attr("sender", &info.sender),
+            attr("round", config.round),
+            attr("rolled_over", rolled_over),
+            attr("budget", config.budget.amount),
+        ],
+        ..Default::default()
+    })
+}
+
+// removes proposals and votes of the distributed round
+fn clear_round(storage: &mut dyn Storage) -> StdResult<()> {
+    let ids: StdResult<Vec<u64>> = PROPOSALS
+        .range(storage, None, None, Order::Ascending)
+        .map(|p| p.map(|(_, p)| p.id))
+        .collect();
+
+    for id in ids? {
+        let voters: StdResult<Vec<Vec<u8>>> = VOTES
+            .prefix(id.into())
+            .range(storage, None, None, Order::Ascending)
+            .map(|v| v.map(|(voter, _)| voter))
+            .collect();
+        for voter in voters? {
+            VOTES.remove(storage, (id.into(), &voter));
+            VOTER_VOTES.remove(storage, (&voter, id.into()));
+        }
+        PROPOSALS.remove(storage, id.into());
+    }
+
+    Ok(())
+}
+
+pub fn handle_pause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only admin can pause
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // pausing again keeps the first pause block
+    if config.paused_at.is_none() {
+        config.paused_at = Some(PausedAt {
+            height: env.block.height,
+            time: env.block.time,
+        });
+    }
+    config.paused = true;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes: vec![attr("action", "pause"), attr("sender", &info.sender)],
+        ..Default::default()
+    })
+}
+
+pub fn handle_unpause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only admin can unpause
+    if !config.is_admin(&deps.api.canonical_address(&info.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // periods still open when paused get the paused time back
+    if let Some(paused_at) = config.paused_at.take() {
+        config.voting_period = extend_period(&config.voting_period, &paused_at, &env.block);
+        config.proposal_period = extend_period(&config.proposal_period, &paused_at, &env.block);
+        if let Some(commit_reveal) = config.commit_reveal.as_mut() {
+            commit_reveal.reveal_period =
+                extend_period(&commit_reveal.reveal_period, &paused_at, &env.block);
+        }
+    }
+    config.paused = false;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes: vec![attr("action", "unpause"), attr("sender", &info.sender)],
+        ..Default::default()
+    })
+}
+
+// voter's cw4 group weight, kept on votes when weights come from the group
+fn cw4_weight(deps: Deps, config: &Config, voter: &CanonicalAddr) -> StdResult<Option<u64>> {
+    match (&config.voter_weight, &config.voter_group) {
+        (Some(VoterWeightSource::Cw4Group {}), Some(group)) => {
+            let res: MemberResponse = deps.querier.query_wasm_smart(
+                deps.api.human_address(group)?,
+                &Cw4QueryMsg::Member {
+                    addr: deps.api.human_address(voter)?,
+                    at_height: None,
+                },
+            )?;
+            Ok(Some(res.weight.unwrap_or(0)))
+        }
+        _ => Ok(None),
+    }
+}
+
+// resolves voter weight from configured source
+fn voter_weight(deps: Deps, config: &Config, vote: &Vote) -> StdResult<u64> {
+    match &config.voter_weight {
+        // votes cast before weights were kept are weighed by the current group weight
+        Some(VoterWeightSource::Cw4Group {}) => match vote.weight {
+            Some(weight) => Ok(weight),
+            None => Ok(cw4_weight(deps, config, &vote.voter)?.unwrap_or(WEIGHT_SCALE)),
+        },
+        Some(VoterWeightSource::Scores {}) => Ok(VOTER_SCORES
+            .may_load(deps.storage, vote.voter.as_slice())?
+            .unwrap_or(WEIGHT_SCALE)),
+        None => Ok(WEIGHT_SCALE),
+    }
+}
+
+// permissionless if neither whitelist nor cw4 group is set
+fn is_whitelisted(
+    deps: Deps,
+    config: &Config,
+    whitelist: Whitelist,
+    addr: &HumanAddr,
+) -> StdResult<bool> {
+    if let Some(group) = whitelist.group(config) {
+        let res: MemberResponse = deps.querier.query_wasm_smart(
+            deps.api.human_address(group)?,
+            &Cw4QueryMsg::Member {
+                addr: addr.clone(),
+                at_height: None,
+            },
+        )?;
+        return Ok(res.weight.is_some());
+    }
+    if !whitelist.is_enabled(config) {
+        return Ok(true);
+    }
+    let addr = deps.api.canonical_address(addr)?;
+    Ok(whitelist
+        .members()
+        .may_load(deps.storage, addr.as_slice())?
+        .is_some())
+}
+
+// checks if any proposal received a vote, committed votes included
+fn any_votes(deps: Deps) -> StdResult<bool> {
+    if any_commitments(deps)? {
+        return Ok(true);
+    }
+    for p in PROPOSALS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, p) = p?;
+        if VOTES
+            .prefix(p.id.into())
+            .range(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some()
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// checks if any hidden vote is committed and not yet revealed
+fn any_commitments(deps: Deps) -> StdResult<bool> {
+    Ok(COMMITMENTS
+        .sub_prefix(())
+        .range(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some())
+}
+
+pub fn migrate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: MigrateMsg,
+) -> Result<MigrateResponse, ContractError> {
+    let previous_version = match CONTRACT.may_load(deps.storage)? {
+        Some(stored) => {
+            if stored.contract != CONTRACT_NAME {
+                return Err(ContractError::CannotMigrate {
+                    previous_contract: stored.contract,
+                });
+            }
+            if parse_version(&stored.version)? > parse_version(CONTRACT_VERSION)? {
+                return Err(ContractError::CannotMigrateVersion {
+                    previous_version: stored.version,
+                    new_version: CONTRACT_VERSION.to_string(),
+                });
+            }
+            stored.version
+        }
+        // deployed before version tracking, state is in the legacy layout
+        None => {
+            migrate_legacy_state(deps.storage)?;
+            "0.1.0".to_string()
+        }
+    };
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(MigrateResponse {
+        attributes: vec![
+            attr("action", "migrate"),
+            attr("previous_version", previous_version),
+            attr("new_version", CONTRACT_VERSION),
+        ],
+        ..Default::default()
+    })
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    Version::parse(version).map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::ProposalByID { id } => to_binary(&query_proposal_id(deps, id)?),
+        QueryMsg::AllProposals { status } => to_binary(&query_all_proposals(deps, status)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::WhitelistMembers {
+            whitelist,
+            start_after,
+            limit,
+        } => to_binary(&query_whitelist_members(
+            deps,
+            whitelist,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::VotesByVoter {
+            voter,
+            start_after,
+            limit,
+        } => to_binary(&query_votes_by_voter(deps, voter, start_after, limit)?),
+        QueryMsg::DelegationsByCurator {
+            curator,
+            start_after,
+            limit,
+        } => to_binary(&query_delegations_by_curator(
+            deps,
+            curator,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::RelayAccount { voter } => to_binary(&query_relay_account(deps, voter)?),
+        QueryMsg::AcceptedDenoms {} => to_binary(&query_accepted_denoms(deps)?),
+        QueryMsg::ProposalMatchBreakdown { id } => {
+            to_binary(&query_proposal_match_breakdown(deps, id)?)
+        }
+        QueryMsg::DistributionResult { round } => {
+            to_binary(&query_distribution_result(deps, round)?)
+        }
+        QueryMsg::Rollovers { start_after, limit } => {
+            to_binary(&query_rollovers(deps, start_after, limit)?)
+        }
+    }
+}
+
+fn query_proposal_id(deps: Deps, id: u64) -> StdResult<Proposal> {
+    PROPOSALS.load(deps.storage, id.into())
+}
+
+fn query_config(deps: Deps) -> StdResult<Config> {
+    CONFIG.load(deps.storage)
+}
+
+fn query_delegations_by_curator(
+    deps: Deps,
+    curator: HumanAddr,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<DelegationsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(addr) => Some(Bound::exclusive(deps.api.canonical_address(&addr)?)),
+        None => None,
+    };
+
+    let curator = deps.api.canonical_address(&curator)?;
+    let delegations: StdResult<Vec<Delegation>> = DELEGATIONS
+        .prefix(curator.as_slice())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, d)| d))
+        .collect();
+
+    Ok(DelegationsResponse {
+        delegations: delegations?,
+    })
+}
+
+fn query_relay_account(deps: Deps, voter: HumanAddr) -> StdResult<RelayAccountResponse> {
+    let voter = deps.api.canonical_address(&voter)?;
+    Ok(RelayAccountResponse {
+        deposit: DEPOSITS.may_load(deps.storage, voter.as_slice())?,
+        nonce: RELAY_NONCES
+            .may_load(deps.storage, voter.as_slice())?
+            .unwrap_or_default(),
+    })
+}
+
+fn query_accepted_denoms(deps: Deps) -> StdResult<AcceptedDenomsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut denoms = vec![DenomRate {
+        denom: config.budget.denom.clone(),
+        rate: Decimal::one(),
+    }];
+    for item in DENOM_RATES.range(deps.storage, None, None, Order::Ascending) {
+        let (denom, rate) = item?;
+        denoms.push(DenomRate {
+            denom: String::from_utf8(denom)?,
+            rate,
+        });
+    }
+    Ok(AcceptedDenomsResponse {
+        denoms,
+        oracle: config
+            .oracle
+            .map(|o| deps.api.human_address(&o))
+            .transpose()?,
+    })
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn query_whitelist_members(
+    deps: Deps,
+    whitelist: Whitelist,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<WhitelistMembersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(addr) => Some(Bound::exclusive(deps.api.canonical_address(&addr)?)),
+        None => None,
+    };
+
+    let members: StdResult<Vec<HumanAddr>> = whitelist
+        .members()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, _) = item?;
+            deps.api.human_address(&CanonicalAddr::from(key))
+        })
+        .collect();
+
+    Ok(WhitelistMembersResponse { members: members? })
+}
+
+fn query_votes_by_voter(
+    deps: Deps,
+    voter: HumanAddr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<VotesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+    let voter = deps.api.canonical_address(&voter)?;
+
+    let votes: StdResult<Vec<Vote>> = VOTER_VOTES
+        .prefix(voter.as_slice())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (proposal_id, _) = item?;
+            VOTES.load(
+                deps.storage,
+                (PkOwned(proposal_id).into(), voter.as_slice()),
+            )
+        })
+        .collect();
+
+    Ok(VotesResponse { votes: votes? })
+}
+
+fn query_proposal_match_breakdown(
+    deps: Deps,
+    id: u64,
+) -> StdResult<ProposalMatchBreakdownResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let proposal = PROPOSALS.load(deps.storage, id.into())?;
+    if proposal.status == ProposalStatus::Rejected {
+        return Err(StdError::generic_err("Proposal is rejected"));
+    }
+
+    // grants are scaled against all proposals, collected as in distribution
+    let mut grants: Vec<RawGrant> = vec![];
+    let mut contributors = 0;
+    for item in PROPOSALS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, p) = item?;
+        if p.status == ProposalStatus::Rejected {
+            continue;
+        }
+        let votes: Vec<Vote> = VOTES
+            .prefix(p.id.into())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|v| v.map(|(_, v)| v))
+            .collect::<StdResult<_>>()?;
+        // counted before the contributor threshold drops votes from matching
+        if p.id == id {
+            contributors = votes.len() as u64;
+        }
+        grants.push(raw_grant(deps, &config, &p, votes)?);
+    }
+
+    let breakdown = match config.algorithm {
+        QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism { .. } => {
+            calculate_clr_breakdown(
+                &grants,
+                id,
+                Some(config.budget.amount.u128()),
+                config.max_matched_contribution.map(|m| m.u128()),
+            )
+        }
+    }
+    .map_err(|e| StdError::generic_err(e.to_string()))?
+    .ok_or_else(|| StdError::not_found("Proposal"))?;
+
+    Ok(ProposalMatchBreakdownResponse {
+        proposal_id: id,
+        contributors,
+        sum_of_square_roots: Uint128(breakdown.sum_sqrts),
+        raw_match: Uint128(breakdown.raw_match),
+        raw_total: Uint128(breakdown.raw_total),
+        budget: Uint128(breakdown.budget),
+        scaling_factor: breakdown.scaling_factor,
+        grant: Uint128(breakdown.grant),
+    })
+}
+
+fn query_distribution_result(deps: Deps, round: Option<u64>) -> StdResult<DistributionResult> {
+    match round {
+        Some(round) => DISTRIBUTION_RESULTS.load(deps.storage, round.into()),
+        None => DISTRIBUTION_RESULTS
+            .range(deps.storage, None, None, Order::Descending)
+            .next()
+            .map(|item| item.map(|(_, r)| r))
+            .unwrap_or_else(|| Err(StdError::not_found("DistributionResult"))),
+    }
+}
+
+fn query_rollovers(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RolloversResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let rollovers: StdResult<Vec<Rollover>> = ROLLOVERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, r)| r))
+        .collect();
+
+    Ok(RolloversResponse {
+        rollovers: rollovers?,
+    })
+}
+
+fn query_all_proposals(
+    deps: Deps,
+    status: Option<ProposalStatus>,
+) -> StdResult<AllProposalsResponse> {
+    let all: StdResult<Vec<(Vec<u8>, Proposal)>> = PROPOSALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect();
+    all.map(|p| {
+        let res = p
+            .into_iter()
+            .map(|x| x.1)
+            .filter(|x| match status {
+                Some(s) => x.status == s,
+                // hidden proposals are only listed when asked for
+                None => x.status != ProposalStatus::Hidden,
+            })
+            .collect();
+
+        AllProposalsResponse { proposals: res }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::{
+        handle, init, migrate, query_accepted_denoms, query_all_proposals, query_config,
+        query_delegations_by_curator, query_distribution_result, query_proposal_id,
+        query_proposal_match_breakdown, query_relay_account, query_rollovers, query_votes_by_voter,
+        query_whitelist_members, CONTRACT_NAME, CONTRACT_VERSION,
+    };
+    use crate::error::ContractError;
+    use crate::helper::{commitment_hash, verify_signature};
+    use crate::matching::{QuadraticFundingAlgorithm, MAX_VOTER_SCORE};
+    use crate::migrations::{LegacyConfig, LEGACY_CONFIG};
+    use crate::msg::{
+        AllProposalsResponse, Allocation, DenomRate, HandleMsg, InitMsg, MigrateMsg,
+        RecipientShare, RelayAccountResponse, RelayedVotePayload, VoterScore,
+    };
+    use crate::state::{
+        CommitReveal, Proposal, ProposalStatus, Rollover, VoterWeightSource, Whitelist, PROPOSALS,
+    };
+    use crate::testing::mock_init_msg;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        attr, coin, from_binary, from_slice, to_binary, Api, BankMsg, Binary, CanonicalAddr,
+        ContractResult, CosmosMsg, Decimal, Empty, HumanAddr, OwnedDeps, Querier, QuerierResult,
+        QueryRequest, StdResult, SystemResult, Uint128, WasmQuery,
+    };
+    use cw0::Expiration;
+    use cw2::{get_contract_version, set_contract_version};
+    use cw4::{Cw4QueryMsg, Member, MemberResponse};
+    use std::collections::HashMap;
+
+    // mock querier answering cw4 member queries of the given groups
+    struct GroupQuerier {
+        base: MockQuerier,
+        groups: HashMap<HumanAddr, Vec<Member>>,
+    }
+
+    impl Querier for GroupQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            match request {
+                QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                    let members = &self.groups[&contract_addr];
+                    match from_binary(&msg).unwrap() {
+                        Cw4QueryMsg::Member { addr, .. } => {
+                            let res = MemberResponse {
+                                weight: members.iter().find(|m| m.addr == addr).map(|m| m.weight),
+                            };
+                            SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+                        }
+                        q => SystemResult::Ok(ContractResult::Err(format!(
+                            "unsupported cw4 query: {:?}",
+                            q
+                        ))),
+                    }
+                }
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    // mock api mapping human addresses to given canonical addresses, e.g. signer addresses
+    struct SignerApi {
+        base: MockApi,
+        addrs: HashMap<HumanAddr, CanonicalAddr>,
+    }
+
+    impl Api for SignerApi {
+        fn canonical_address(&self, human: &HumanAddr) -> StdResult<CanonicalAddr> {
+            match self.addrs.get(human) {
+                Some(addr) => Ok(addr.clone()),
+                None => self.base.canonical_address(human),
+            }
+        }
+
+        fn human_address(&self, canonical: &CanonicalAddr) -> StdResult<HumanAddr> {
+            match self.addrs.iter().find(|(_, addr)| *addr == canonical) {
+                Some((human, _)) => Ok(human.clone()),
+                None => self.base.human_address(canonical),
+            }
+        }
+
+        fn debug(&self, message: &str) {
+            self.base.debug(message)
+        }
+    }
+
+    fn mock_dependencies_with_groups(
+        groups: &[(&str, &[(&str, u64)])],
+    ) -> OwnedDeps<MockStorage, MockApi, GroupQuerier> {
+        let groups = groups
+            .iter()
+            .map(|(group, members)| {
+                let members = members
+                    .iter()
+                    .map(|(addr, weight)| Member {
+                        addr: HumanAddr::from(*addr),
+                        weight: *weight,
+                    })
+                    .collect();
+                (HumanAddr::from(*group), members)
+            })
+            .collect();
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: GroupQuerier {
+                base: MockQuerier::new(&[]),
+                groups,
+            },
+        }
+    }
+
+    #[test]
+    fn create_proposal() {
+        let mut env = mock_env();
+        let info = mock_info("addr", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            admin: HumanAddr::from("addr"),
+            ..mock_init_msg(&env)
+        };
+
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("test"),
+            description: String::from("test"),
+            metadata: Some(b"test".into()),
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        // success case
+        match res {
+            Ok(seq) => assert_eq!(seq.data.unwrap(), Binary::from(1_u64.to_be_bytes())),
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // empty title
+        let mut invalid_msg = msg.clone();
+        if let HandleMsg::CreateProposal { ref mut title, .. } = invalid_msg {
+            *title = String::from("  ");
+        }
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), invalid_msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::EmptyTitle {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // title too long
+        let mut invalid_msg = msg.clone();
+        if let HandleMsg::CreateProposal { ref mut title, .. } = invalid_msg {
+            *title = "t".repeat(129);
+        }
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), invalid_msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::TitleLengthOutOfBounds { got: 129, .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // description too long
+        let mut invalid_msg = msg.clone();
+        if let HandleMsg::CreateProposal {
+            ref mut description,
+            ..
+        } = invalid_msg
+        {
+            *description = "d".repeat(4097);
+        }
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), invalid_msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::DescriptionLengthOutOfBounds { got: 4097, .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // metadata too long
+        let mut invalid_msg = msg.clone();
+        if let HandleMsg::CreateProposal {
+            ref mut metadata, ..
+        } = invalid_msg
+        {
+            *metadata = Some(Binary::from(vec![0u8; 4097]));
+        }
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), invalid_msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::MetadataLengthOutOfBounds { got: 4097, .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // fund address is the contract itself
+        let mut invalid_msg = msg.clone();
+        if let HandleMsg::CreateProposal {
+            ref mut fund_address,
+            ..
+        } = invalid_msg
+        {
+            *fund_address = env.contract.address.clone();
+        }
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), invalid_msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::FundAddressIsContract {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // proposal period expired
+        env.block.height += 1000;
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalPeriodExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // unauthorised
+        let env = mock_env();
+        let info = mock_info("true", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+        let init_msg = InitMsg {
+            admin: HumanAddr::from("person"),
+            create_proposal_whitelist: Some(vec![HumanAddr::from("false")]),
+            voting_period: Default::default(),
+            proposal_period: Default::default(),
+            ..init_msg
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn vote_proposal() {
+        let mut env = mock_env();
+        let info = mock_info("addr", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let mut init_msg = InitMsg {
+            admin: HumanAddr::from("addr"),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
+
+        let create_proposal_msg = HandleMsg::CreateProposal {
+            title: String::from("test"),
+            description: String::from("test"),
+            metadata: Some(Binary::from(b"test")),
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_proposal_msg.clone(),
+        );
+        match res {
+            Ok(seq) => assert_eq!(seq.data.unwrap(), Binary::from(1_u64.to_be_bytes())),
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        // success case
+        match res {
+            Ok(res) => assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "vote_proposal"),
+                    attr("sender", "addr"),
+                    attr("proposal_id", 1),
+                    attr("amount", 1000),
+                    attr("denom", "ucosm"),
+                    attr("value", 1000),
+                    attr("collected_funds", 1000),
+                ]
+            ),
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // double vote prevention
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::AddressAlreadyVotedProject {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // whitelist check
+        let mut deps = mock_dependencies(&[]);
+        init_msg.vote_proposal_whitelist = Some(vec![HumanAddr::from("admin")]);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // proposal period expired
+        let mut deps = mock_dependencies(&[]);
+        init_msg.vote_proposal_whitelist = None;
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
+        env.block.height += 15;
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::VotingPeriodExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn update_and_withdraw_proposal() {
+        let mut env = mock_env();
+        let info = mock_info("proposer", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            admin: HumanAddr::from("addr"),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        let create_proposal_msg = HandleMsg::CreateProposal {
+            title: String::from("tset"),
+            description: String::from("test"),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_proposal_msg.clone(),
+        )
+        .unwrap();
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_proposal_msg,
+        )
+        .unwrap();
+
+        let update_msg = HandleMsg::UpdateProposal {
+            id: 1,
+            title: Some(String::from("test")),
+            description: None,
+            metadata: None,
+            fund_address: Some(HumanAddr::from("new_fund_address")),
+            recipients: None,
+        };
+
+        // only proposer can update
+        let other = mock_info("other", &[]);
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            other.clone(),
+            update_msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // success case
+        handle(deps.as_mut(), env.clone(), info.clone(), update_msg.clone()).unwrap();
+        let proposal = query_proposal_id(deps.as_ref(), 1).unwrap();
+        assert_eq!(proposal.title, "test");
+        assert_eq!(proposal.description, "test");
+        assert_eq!(
+            deps.api.human_address(&proposal.fund_address).unwrap(),
+            HumanAddr::from("new_fund_address")
+        );
+
+        // updated fields are validated
+        let invalid_msg = HandleMsg::UpdateProposal {
+            id: 1,
+            title: Some(String::from("")),
+            description: None,
+            metadata: None,
+            fund_address: None,
+            recipients: None,
+        };
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), invalid_msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::EmptyTitle {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // only proposer can withdraw
+        let withdraw_msg = HandleMsg::WithdrawProposal { id: 2 };
+        let res = handle(deps.as_mut(), env.clone(), other, withdraw_msg.clone());
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // withdraw removes proposal
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            withdraw_msg.clone(),
+        )
+        .unwrap();
+        assert!(query_proposal_id(deps.as_ref(), 2).is_err());
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), withdraw_msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalNotFound {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // proposal is locked after a vote
+        let voter = mock_info("voter", &[coin(100, "ucosm")]);
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            voter,
+            HandleMsg::VoteProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), update_msg.clone());
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalHasVotes {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            HandleMsg::WithdrawProposal { id: 1 },
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalHasVotes {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // proposal period expired
+        env.block.height += 10;
+        let res = handle(deps.as_mut(), env, info, update_msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalPeriodExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn moderate_proposals() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = mock_init_msg(&env);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        for i in 1..=2 {
+            let msg = HandleMsg::CreateProposal {
+                title: format!("proposal {}", i),
+                description: "".to_string(),
+                metadata: None,
+                fund_address: HumanAddr::from(format!("fund_address{}", i)),
+                recipients: None,
+            };
+            handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let voter = mock_info(format!("voter{}", i), &[coin(100, "ucosm")]);
+            let msg = HandleMsg::VoteProposal { proposal_id: i };
+            handle(deps.as_mut(), env.clone(), voter, msg).unwrap();
+        }
+
+        // only admin can moderate
+        let reject_msg = HandleMsg::SetProposalStatus {
+            id: 2,
+            status: ProposalStatus::Rejected,
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            reject_msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        handle(deps.as_mut(), env.clone(), info.clone(), reject_msg).unwrap();
+        let msg = HandleMsg::SetProposalStatus {
+            id: 1,
+            status: ProposalStatus::Hidden,
+        };
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // rejected proposal can't be voted
+        let voter = mock_info("voter3", &[coin(100, "ucosm")]);
+        let msg = HandleMsg::VoteProposal { proposal_id: 2 };
+        match handle(deps.as_mut(), env.clone(), voter, msg) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalRejected {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // filter by status
+        let res = query_all_proposals(deps.as_ref(), Some(ProposalStatus::Rejected)).unwrap();
+        assert_eq!(res.proposals.len(), 1);
+        assert_eq!(res.proposals[0].id, 2);
+        let res = query_all_proposals(deps.as_ref(), Some(ProposalStatus::Active)).unwrap();
+        assert!(res.proposals.is_empty());
+        let res = query_all_proposals(deps.as_ref(), Some(ProposalStatus::Hidden)).unwrap();
+        assert_eq!(res.proposals.len(), 1);
+        assert_eq!(res.proposals[0].id, 1);
+
+        // hidden proposals are kept out of unfiltered listings
+        let res = query_all_proposals(deps.as_ref(), None).unwrap();
+        let ids: Vec<u64> = res.proposals.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![2]);
+
+        // rejected proposal votes are refunded, hidden proposal still gets matched
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("fund_address1"),
+                amount: vec![coin(1100u128, "ucosm")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("voter2"),
+                amount: vec![coin(100u128, "ucosm")],
+            }),
+        ];
+        assert_eq!(expected_msgs, res.messages);
+    }
+
+    #[test]
+    fn proposal_deposit() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            proposal_deposit: Some(coin(50, "udeposit")),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        // deposit is queryable
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(config.proposal_deposit, Some(coin(50, "udeposit")));
+
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("proposal"),
+            description: "".to_string(),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+
+        // deposit missing
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("proposer1", &[]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::WrongCoinSent {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // wrong deposit amount
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("proposer1", &[coin(49, "udeposit")]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::WrongProposalDeposit { .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        for i in 1..=3 {
+            let proposer = mock_info(format!("proposer{}", i), &[coin(50, "udeposit")]);
+            handle(deps.as_mut(), env.clone(), proposer, msg.clone()).unwrap();
+        }
+        let proposal = query_proposal_id(deps.as_ref(), 1).unwrap();
+        assert_eq!(proposal.deposit, Some(coin(50, "udeposit")));
+
+        // withdrawal returns deposit
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("proposer3", &[]),
+            HandleMsg::WithdrawProposal { id: 3 },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("proposer3"),
+                amount: vec![coin(50, "udeposit")],
+            })]
+        );
+
+        let msg = HandleMsg::SetProposalStatus {
+            id: 2,
+            status: ProposalStatus::Rejected,
+        };
+        handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // rejected proposal can't be withdrawn to escape slashing
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("proposer2", &[]),
+            HandleMsg::WithdrawProposal { id: 2 },
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalRejected {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // deposit refunded to proposer 1, slashed for rejected proposal 2,
+        // no zero coin grant is sent to proposal 1
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("proposer1"),
+                amount: vec![coin(50u128, "udeposit")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("addr"),
+                amount: vec![coin(50u128, "udeposit")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("addr"),
+                amount: vec![coin(1000u128, "ucosm")],
+            }),
+        ];
+        assert_eq!(expected_msgs, res.messages);
+    }
+
+    #[test]
+    fn update_config() {
+        let mut env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = mock_init_msg(&env);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        let msg = HandleMsg::UpdateConfig {
+            leftover_addr: Some(HumanAddr::from("new_addr")),
+            rollover_leftover: None,
+            oracle: None,
+            voting_period: Some(Expiration::AtHeight(env.block.height + 30)),
+            proposal_period: Some(Expiration::AtHeight(env.block.height + 20)),
+            algorithm: Some(
+                QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
+                    parameter: "new".to_string(),
+                },
+            ),
+        };
+
+        // only admin can update config
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // success case
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(
+            deps.api.human_address(&config.leftover_addr).unwrap(),
+            HumanAddr::from("new_addr")
+        );
+        assert_eq!(
+            config.voting_period,
+            Expiration::AtHeight(env.block.height + 30)
+        );
+        assert_eq!(
+            config.proposal_period,
+            Expiration::AtHeight(env.block.height + 20)
+        );
+
+        // period can't be set to the past
+        let msg = HandleMsg::UpdateConfig {
+            leftover_addr: None,
+            rollover_leftover: None,
+            oracle: None,
+            voting_period: Some(Expiration::AtHeight(env.block.height - 1)),
+            proposal_period: None,
+            algorithm: None,
+        };
+        match handle(deps.as_mut(), env.clone(), info.clone(), msg) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::VotingPeriodExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // algorithm can't be changed after voting started
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("proposal"),
+            description: "".to_string(),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &[coin(100, "ucosm")]),
+            msg,
+        )
+        .unwrap();
+        let msg = HandleMsg::UpdateConfig {
+            leftover_addr: None,
+            rollover_leftover: None,
+            oracle: None,
+            voting_period: None,
+            proposal_period: None,
+            algorithm: Some(
+                QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
+                    parameter: "".to_string(),
+                },
+            ),
+        };
+        match handle(deps.as_mut(), env.clone(), info.clone(), msg) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::VotingStarted {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // expired proposal period can't be reopened
+        env.block.height += 20;
+        let msg = HandleMsg::UpdateConfig {
+            leftover_addr: None,
+            rollover_leftover: None,
+            oracle: None,
+            voting_period: None,
+            proposal_period: Some(Expiration::AtHeight(env.block.height + 5)),
+            algorithm: None,
+        };
+        match handle(deps.as_mut(), env, info, msg) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalPeriodExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn transfer_admin() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = mock_init_msg(&env);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        // nothing to accept
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("multisig", &[]),
+            HandleMsg::AcceptAdmin {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::NoPendingAdmin {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // only admin can propose
+        let msg = HandleMsg::ProposeNewAdmin {
+            addr: HumanAddr::from("multisig"),
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("multisig", &[]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(
+            config.pending_admin,
+            Some(
+                deps.api
+                    .canonical_address(&HumanAddr::from("multisig"))
+                    .unwrap()
+            )
+        );
+
+        // only pending admin can accept
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            HandleMsg::AcceptAdmin {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("multisig", &[]),
+            HandleMsg::AcceptAdmin {},
+        )
+        .unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(
+            config.admin,
+            Some(
+                deps.api
+                    .canonical_address(&HumanAddr::from("multisig"))
+                    .unwrap()
+            )
+        );
+        assert_eq!(config.pending_admin, None);
+
+        // old admin lost rights
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            HandleMsg::RenounceAdmin {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // renounce admin, distribution can be triggered by anyone
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("multisig", &[]),
+            HandleMsg::RenounceAdmin {},
+        )
+        .unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(config.admin, None);
+
+        let mut env = mock_env();
+        env.block.height += 1000;
+        handle(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn manage_whitelist() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            vote_proposal_whitelist: Some(vec![HumanAddr::from("voter1")]),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("proposal"),
+            description: "".to_string(),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let vote_msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[coin(100, "ucosm")]),
+            vote_msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // only admin can manage whitelist
+        let add_msg = HandleMsg::AddToWhitelist {
+            whitelist: Whitelist::VoteProposal,
+            addrs: vec![HumanAddr::from("voter2"), HumanAddr::from("voter3")],
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            add_msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // proposal whitelist wasn't set at init
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            HandleMsg::AddToWhitelist {
+                whitelist: Whitelist::CreateProposal,
+                addrs: vec![HumanAddr::from("proposer")],
+            },
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::WhitelistNotEnabled {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        handle(deps.as_mut(), env.clone(), info.clone(), add_msg).unwrap();
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[coin(100, "ucosm")]),
+            vote_msg.clone(),
+        )
+        .unwrap();
+
+        let remove_msg = HandleMsg::RemoveFromWhitelist {
+            whitelist: Whitelist::VoteProposal,
+            addrs: vec![HumanAddr::from("voter1")],
+        };
+        handle(deps.as_mut(), env.clone(), info.clone(), remove_msg).unwrap();
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[coin(100, "ucosm")]),
+            vote_msg,
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // paginated members query
+        let res =
+            query_whitelist_members(deps.as_ref(), Whitelist::VoteProposal, None, Some(1)).unwrap();
+        assert_eq!(res.members, vec![HumanAddr::from("voter2")]);
+        let res = query_whitelist_members(
+            deps.as_ref(),
+            Whitelist::VoteProposal,
+            Some(HumanAddr::from("voter2")),
+            None,
+        )
+        .unwrap();
+        assert_eq!(res.members, vec![HumanAddr::from("voter3")]);
+        let res =
+            query_whitelist_members(deps.as_ref(), Whitelist::CreateProposal, None, None).unwrap();
+        assert!(res.members.is_empty());
+    }
+
+    #[test]
+    fn cw4_group_registry() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies_with_groups(&[
+            ("proposer_group", &[("proposer", 1)]),
+            ("voter_group", &[("voter", 1)]),
+        ]);
+
+        let init_msg = InitMsg {
+            proposer_group: Some(HumanAddr::from("proposer_group")),
+            voter_group: Some(HumanAddr::from("voter_group")),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("proposal"),
+            description: "".to_string(),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+        match handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        handle(deps.as_mut(), env.clone(), mock_info("proposer", &[]), msg).unwrap();
+
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("proposer", &[coin(100, "ucosm")]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &[coin(100, "ucosm")]),
+            msg,
+        )
+        .unwrap();
+
+        // group members are managed in the group contract
+        let res = handle(
+            deps.as_mut(),
+            env,
+            info,
+            HandleMsg::AddToWhitelist {
+                whitelist: Whitelist::VoteProposal,
+                addrs: vec![HumanAddr::from("other")],
+            },
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::WhitelistAndGroupConflict {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn voter_weights() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps =
+            mock_dependencies_with_groups(&[("voter_group", &[("voter1", 200), ("voter2", 100)])]);
+
+        let mut init_msg = InitMsg {
+            voter_weight: Some(VoterWeightSource::Scores {}),
+            ..mock_init_msg(&env)
+        };
+
+        let setup = |deps: &mut OwnedDeps<MockStorage, MockApi, GroupQuerier>| {
+            for i in 1..=2 {
+                let msg = HandleMsg::CreateProposal {
+                    title: format!("proposal {}", i),
+                    description: "".to_string(),
+                    metadata: None,
+                    fund_address: HumanAddr::from(format!("fund_address{}", i)),
+                    recipients: None,
+                };
+                handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+                let voter = mock_info(format!("voter{}", i), &[coin(10000, "ucosm")]);
+                let msg = HandleMsg::VoteProposal { proposal_id: i };
+                handle(deps.as_mut(), env.clone(), voter, msg).unwrap();
+            }
+        };
+        let distribute = |deps: &mut OwnedDeps<MockStorage, MockApi, GroupQuerier>| {
+            let mut env = mock_env();
+            env.block.height += 1000;
+            let msg = HandleMsg::TriggerDistribution {};
+            handle(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg)
+                .unwrap()
+                .messages
+        };
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("fund_address1"),
+                amount: vec![coin(10800u128, "ucosm")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("fund_address2"),
+                amount: vec![coin(10200u128, "ucosm")],
+            }),
+        ];
+
+        // admin uploaded scores
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
+        setup(&mut deps);
+        let msg = HandleMsg::SetVoterScores {
+            scores: vec![VoterScore {
+                addr: HumanAddr::from("voter1"),
+                score: 200,
+            }],
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            HandleMsg::SetVoterScores {
+                scores: vec![VoterScore {
+                    addr: HumanAddr::from("voter2"),
+                    score: MAX_VOTER_SCORE + 1,
+                }],
+            },
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::VoterScoreTooHigh { .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(expected_msgs, distribute(&mut deps));
+
+        // cw4 member weights
+        let mut deps =
+            mock_dependencies_with_groups(&[("voter_group", &[("voter1", 200), ("voter2", 100)])]);
+        init_msg.voter_group = Some(HumanAddr::from("voter_group"));
+        init_msg.voter_weight = Some(VoterWeightSource::Cw4Group {});
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+        setup(&mut deps);
+        // weights are kept at vote time, later group changes don't affect matching
+        deps.querier =
+            mock_dependencies_with_groups(&[("voter_group", &[("voter2", 300)])]).querier;
+        assert_eq!(expected_msgs, distribute(&mut deps));
+    }
+
+    #[test]
+    fn contribution_thresholds() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            min_contribution: Some(Uint128(100)),
+            min_unique_contributors: Some(2),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        for i in 1..=2 {
+            let msg = HandleMsg::CreateProposal {
+                title: format!("proposal {}", i),
+                description: "".to_string(),
+                metadata: None,
+                fund_address: HumanAddr::from(format!("fund_address{}", i)),
+                recipients: None,
+            };
+            handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // dust vote
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[coin(99, "ucosm")]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ContributionTooLow { min: 100, got: 99 }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        for voter in &["voter1", "voter2"] {
+            let info = mock_info(*voter, &[coin(100, "ucosm")]);
+            handle(deps.as_mut(), env.clone(), info, msg.clone()).unwrap();
+        }
+        let msg = HandleMsg::VoteProposal { proposal_id: 2 };
+        let info = mock_info("voter3", &[coin(400, "ucosm")]);
+        handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // breakdown still reports the votes left out of matching
+        let breakdown = query_proposal_match_breakdown(deps.as_ref(), 2).unwrap();
+        assert_eq!(breakdown.contributors, 1);
+        assert_eq!(breakdown.raw_match, Uint128::zero());
+
+        // proposal 2 is under contributor threshold, only gets its contributions
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("fund_address1"),
+                amount: vec![coin(1200u128, "ucosm")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("fund_address2"),
+                amount: vec![coin(400u128, "ucosm")],
+            }),
+        ];
+        assert_eq!(expected_msgs, res.messages);
+    }
+
+    #[test]
+    fn max_matched_contribution() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            max_matched_contribution: Some(Uint128(10000)),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        for (i, fund) in [1000000u128, 10000u128].iter().enumerate() {
+            let id = i as u64 + 1;
+            let msg = HandleMsg::CreateProposal {
+                title: format!("proposal {}", id),
+                description: "".to_string(),
+                metadata: None,
+                fund_address: HumanAddr::from(format!("fund_address{}", id)),
+                recipients: None,
+            };
+            handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let voter = mock_info(format!("voter{}", id), &[coin(*fund, "ucosm")]);
+            let msg = HandleMsg::VoteProposal { proposal_id: id };
+            handle(deps.as_mut(), env.clone(), voter, msg).unwrap();
+        }
+
+        // both collected and matched funds are shown
+        let proposal = query_proposal_id(deps.as_ref(), 1).unwrap();
+        assert_eq!(proposal.collected_funds, Uint128(1000000));
+        assert_eq!(proposal.matched_funds, Uint128(10000));
+
+        // excess is paid directly without being matched
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("fund_address1"),
+                amount: vec![coin(1000500u128, "ucosm")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("fund_address2"),
+                amount: vec![coin(10500u128, "ucosm")],
+            }),
+        ];
+        assert_eq!(expected_msgs, res.messages);
+    }
+
+    #[test]
+    fn recipient_splits() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            max_recipients: Some(3),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        let share = |addr: &str, share: u64| RecipientShare {
+            addr: HumanAddr::from(addr),
+            share,
+        };
+
+        // shares must sum up to 10000
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("test"),
+            description: String::from("test"),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: Some(vec![share("member1", 5000), share("member2", 4000)]),
+        };
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidRecipientShares { total: 9000 }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("test"),
+            description: String::from("test"),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: Some(vec![
+                share("member1", 2500),
+                share("member2", 2500),
+                share("member3", 2500),
+                share("member4", 2500),
+            ]),
+        };
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::TooManyRecipients { max: 3, got: 4 }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("test"),
+            description: String::from("test"),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: Some(vec![
+                share("member1", 3333),
+                share("member2", 3333),
+                share("member3", 3334),
+            ]),
+        };
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let proposal = query_proposal_id(deps.as_ref(), 1).unwrap();
+        assert_eq!(proposal.recipients.len(), 3);
+
+        let voter = mock_info("voter", &[coin(101, "ucosm")]);
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        handle(deps.as_mut(), env.clone(), voter, msg).unwrap();
+
+        // payout is split by shares, rounding remainder goes to first recipient
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("member1"),
+                amount: vec![coin(368u128, "ucosm")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("member2"),
+                amount: vec![coin(366u128, "ucosm")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("member3"),
+                amount: vec![coin(367u128, "ucosm")],
+            }),
+        ];
+        assert_eq!(expected_msgs, res.messages);
+        let expected_attributes = vec![
+            attr("action", "trigger_distribution"),
+            attr("sender", "admin"),
+            attr("round", 1),
+            attr("proposal_id", 1),
+            attr("grant", 1000),
+            attr("contributions", 101),
+            attr("recipient", "member1"),
+            attr("payout", 368),
+            attr("recipient", "member2"),
+            attr("payout", 366),
+            attr("recipient", "member3"),
+            attr("payout", 367),
+            attr("total_grants", 1000),
+            attr("total_contributions", 101),
+            attr("refunded_votes", 0),
+            attr("leftover", 0),
+        ];
+        assert_eq!(expected_attributes, res.attributes);
+    }
+
+    #[test]
+    fn rollover_next_round() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            rollover_leftover: Some(true),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        // admin has to stay to start the round the leftover is rolled over to
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            HandleMsg::RenounceAdmin {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::RolloverPending {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // proposal without votes leaves the whole budget over
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("test"),
+            description: String::from("test"),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let next_round_msg = HandleMsg::StartNextRound {
+            voting_period: Expiration::AtHeight(env.block.height + 15),
+            proposal_period: Expiration::AtHeight(env.block.height + 10),
+            reveal_period: None,
+        };
+
+        // round must be distributed before the next starts
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            next_round_msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::RoundNotDistributed {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // leftover is kept in the contract
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        // nothing is sent for the proposal without votes
+        assert!(res.messages.is_empty());
+        assert!(res.attributes.contains(&attr("payout", 0)));
+
+        let rollovers = query_rollovers(deps.as_ref(), None, None).unwrap();
+        assert_eq!(
+            rollovers.rollovers,
+            vec![Rollover {
+                round: 1,
+                amount: coin(1000, "ucosm"),
+                height: env.block.height,
+            }]
+        );
+
+        // round can't be distributed twice
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::AlreadyDistributed {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // only admin can start next round
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            next_round_msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // next round budget is the leftover plus top up
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[coin(500, "ucosm")]),
+            next_round_msg,
+        )
+        .unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(config.round, 2);
+        assert!(!config.distributed);
+        assert_eq!(config.budget, coin(1500, "ucosm"));
+        let proposals = query_all_proposals(deps.as_ref(), None).unwrap();
+        assert!(proposals.proposals.is_empty());
+    }
+
+    #[test]
+    fn pause_contract() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = mock_init_msg(&env);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        // only admin can pause
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            HandleMsg::Pause {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            HandleMsg::Pause {},
+        )
+        .unwrap();
+        assert!(query_config(deps.as_ref()).unwrap().paused);
+
+        // actions are rejected while paused
+        let create_msg = HandleMsg::CreateProposal {
+            title: String::from("test"),
+            description: String::from("test"),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), create_msg.clone());
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Paused {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter", &[coin(100, "ucosm")]),
+            HandleMsg::VoteProposal { proposal_id: 1 },
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Paused {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            HandleMsg::TriggerDistribution {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Paused {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // only admin can unpause
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            HandleMsg::Unpause {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // pause runs past the proposal period, open periods are extended by its length
+        let mut unpause_env = env.clone();
+        unpause_env.block.height += 20;
+        handle(
+            deps.as_mut(),
+            unpause_env.clone(),
+            info.clone(),
+            HandleMsg::Unpause {},
+        )
+        .unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        assert!(!config.paused);
+        assert_eq!(config.paused_at, None);
+        assert_eq!(
+            config.proposal_period,
+            Expiration::AtHeight(env.block.height + 30)
+        );
+        assert_eq!(
+            config.voting_period,
+            Expiration::AtHeight(env.block.height + 35)
+        );
+        handle(deps.as_mut(), unpause_env, info, create_msg).unwrap();
+    }
+
+    #[test]
+    fn migrate_contract() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = mock_init_msg(&env);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        // init stores contract version
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // same version migrates
+        migrate(deps.as_mut(), env.clone(), info.clone(), MigrateMsg {}).unwrap();
+
+        // downgrade is refused
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let res = migrate(deps.as_mut(), env.clone(), info.clone(), MigrateMsg {});
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::CannotMigrateVersion { .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // unknown contract is refused
+        set_contract_version(&mut deps.storage, "crates.io:other", "0.1.0").unwrap();
+        let res = migrate(deps.as_mut(), env.clone(), info.clone(), MigrateMsg {});
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::CannotMigrate { .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // contract without version info is migrated from legacy layout
+        let mut deps = mock_dependencies(&[]);
+        let legacy = LegacyConfig {
+            admin: deps.api.canonical_address(&"admin".into()).unwrap(),
+            leftover_addr: deps.api.canonical_address(&"addr".into()).unwrap(),
+            create_proposal_whitelist: None,
+            vote_proposal_whitelist: None,
+            voting_period: Expiration::AtHeight(env.block.height + 15),
+            proposal_period: Expiration::AtHeight(env.block.height + 10),
+            budget: coin(1000, "ucosm"),
+            algorithm: QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
+                parameter: "".to_string(),
+            },
+        };
+        LEGACY_CONFIG.save(&mut deps.storage, &legacy).unwrap();
+        migrate(deps.as_mut(), env.clone(), info, MigrateMsg {}).unwrap();
+
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(config.admin, Some(legacy.admin));
+        assert_eq!(config.budget, coin(1000, "ucosm"));
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+    }
+
+    #[test]
+    fn multi_denom_contributions() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let mut init_msg = InitMsg {
+            accepted_denoms: Some(vec![DenomRate {
+                denom: String::from("ucosm"),
+                rate: Decimal::one(),
+            }]),
+            ..mock_init_msg(&env)
+        };
+
+        // budget denom rate is fixed
+        let res = init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone());
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidDenomRate { .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        init_msg.accepted_denoms = Some(vec![DenomRate {
+            denom: String::from("uosmo"),
+            rate: Decimal::percent(50),
+        }]);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        for fund_address in &["fund_address1", "fund_address2"] {
+            let msg = HandleMsg::CreateProposal {
+                title: String::from("test"),
+                description: String::from("test"),
+                metadata: None,
+                fund_address: HumanAddr::from(*fund_address),
+                recipients: None,
+            };
+            handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // unknown denoms are rejected
+        let msg = HandleMsg::VoteProposal { proposal_id: 2 };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter3", &[coin(100, "uatom")]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::DenomNotAccepted { denom }) => assert_eq!(denom, "uatom"),
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // only admin can set rates
+        let rates = vec![DenomRate {
+            denom: String::from("uatom"),
+            rate: Decimal::percent(200),
+        }];
+        let set_rates = HandleMsg::SetDenomRates { rates };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter3", &[]),
+            set_rates.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let zero_rate = HandleMsg::SetDenomRates {
+            rates: vec![DenomRate {
+                denom: String::from("uatom"),
+                rate: Decimal::zero(),
+            }],
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            zero_rate,
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidDenomRate { .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            set_rates,
+        )
+        .unwrap();
+
+        let res = query_accepted_denoms(deps.as_ref()).unwrap();
+        let denoms: Vec<(String, Decimal)> =
+            res.denoms.into_iter().map(|d| (d.denom, d.rate)).collect();
+        assert_eq!(
+            denoms,
+            vec![
+                (String::from("ucosm"), Decimal::one()),
+                (String::from("uatom"), Decimal::percent(200)),
+                (String::from("uosmo"), Decimal::percent(50)),
+            ]
+        );
+
+        // votes are valued in budget units at vote time
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter3", &[coin(100, "uatom")]),
+            msg,
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("value", 200)));
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter1", &[coin(1000, "uosmo")]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("denom", "uosmo")));
+        assert!(res.attributes.contains(&attr("value", 500)));
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter2", &[coin(500, "ucosm")]),
+            msg,
+        )
+        .unwrap();
+        let proposal = query_proposal_id(deps.as_ref(), 1).unwrap();
+        assert_eq!(proposal.collected_funds, Uint128(1000));
+
+        // removed denoms are no longer accepted, existing votes are kept
+        let msg = HandleMsg::RemoveDenoms {
+            denoms: vec![String::from("uatom")],
+        };
+        handle(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+        let res = query_accepted_denoms(deps.as_ref()).unwrap();
+        assert_eq!(res.denoms.len(), 2);
+
+        // direct contributions are paid out in their original denoms
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        let result = query_distribution_result(deps.as_ref(), None).unwrap();
+        let grant = |id: u64| {
+            result
+                .grants
+                .iter()
+                .find(|g| g.proposal_id == id)
+                .unwrap()
+                .grant
+        };
+        assert_eq!(result.grants[0].collected_vote_funds, Uint128(1000));
+        assert_eq!(result.grants[1].collected_vote_funds, Uint128(200));
+        assert_eq!(
+            res.messages[..2].to_vec(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    from_address: env.contract.address.clone(),
+                    to_address: HumanAddr::from("fund_address1"),
+                    amount: vec![coin(grant(1).u128() + 500, "ucosm"), coin(1000, "uosmo"),],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    from_address: env.contract.address.clone(),
+                    to_address: HumanAddr::from("fund_address2"),
+                    amount: vec![coin(grant(2).u128(), "ucosm"), coin(100, "uatom")],
+                }),
+            ]
+        );
+        assert!(res.attributes.contains(&attr("direct_payout", "1000uosmo")));
+        assert!(res.attributes.contains(&attr("direct_payout", "100uatom")));
+    }
+
+    #[test]
+    fn commit_reveal_voting() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            commit_reveal: Some(CommitReveal {
+                reveal_period: Expiration::AtHeight(env.block.height + 20),
+                refund_unrevealed: true,
+            }),
+            ..mock_init_msg(&env)
+        };
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("test"),
+            description: String::from("test"),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address"),
+            recipients: None,
+        };
+        handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // votes can't be cast publicly
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        let voter1 = mock_info("voter1", &[coin(100, "ucosm")]);
+        let res = handle(deps.as_mut(), env.clone(), voter1.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::CommitRevealEnabled {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let commit = |voter: &str, salt: &[u8]| HandleMsg::CommitVote {
+            commitment: commitment_hash(&HumanAddr::from(voter), 1, salt),
+        };
+        let voter2 = mock_info("voter2", &[coin(400, "ucosm")]);
+        let voter3 = mock_info("voter3", &[coin(50, "ucosm")]);
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            voter1.clone(),
+            commit("voter1", b"salt1"),
+        )
+        .unwrap();
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            voter2.clone(),
+            commit("voter2", b"salt2"),
+        )
+        .unwrap();
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            voter3,
+            commit("voter3", b"salt3"),
+        )
+        .unwrap();
+
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            voter1.clone(),
+            commit("voter1", b"salt1"),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::DuplicateCommitment {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // voting can't be extended past the reveal period
+        let msg = HandleMsg::UpdateConfig {
+            leftover_addr: None,
+            rollover_leftover: None,
+            voting_period: Some(Expiration::AtHeight(env.block.height + 25)),
+            proposal_period: None,
+            algorithm: None,
+            oracle: None,
+        };
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidRevealPeriod {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // proposals are locked once votes are committed
+        let msg = HandleMsg::UpdateProposal {
+            id: 1,
+            title: None,
+            description: None,
+            metadata: None,
+            fund_address: Some(HumanAddr::from("other_address")),
+            recipients: None,
+        };
+        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ProposalHasVotes {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // vote committed on a proposal that gets rejected
+        let voter4 = mock_info("voter4", &[coin(200, "ucosm")]);
+        let msg = HandleMsg::CommitVote {
+            commitment: commitment_hash(&voter4.sender, 2, b"salt4"),
+        };
+        handle(deps.as_mut(), env.clone(), voter4.clone(), msg).unwrap();
+        let msg = HandleMsg::SetProposalStatus {
+            id: 2,
+            status: ProposalStatus::Rejected,
+        };
+        handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // votes are revealed after voting
+        let reveal = |salt: &[u8]| HandleMsg::RevealVote {
+            proposal_id: 1,
+            salt: Binary::from(salt),
+        };
+        let res = handle(deps.as_mut(), env.clone(), voter1.clone(), reveal(b"salt1"));
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::VotingPeriodNotExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let mut env = mock_env();
+        env.block.height += 16;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            voter1.clone(),
+            commit("voter1", b"late"),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::VotingPeriodExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let res = handle(deps.as_mut(), env.clone(), voter1.clone(), reveal(b"salt2"));
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::CommitmentNotFound {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let res = handle(deps.as_mut(), env.clone(), voter1.clone(), reveal(b"salt1")).unwrap();
+        assert!(res.attributes.contains(&attr("collected_funds", 100)));
+        handle(deps.as_mut(), env.clone(), voter2.clone(), reveal(b"salt2")).unwrap();
+        let res = handle(deps.as_mut(), env.clone(), voter2, reveal(b"salt2"));
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::CommitmentNotFound {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let proposal = query_proposal_id(deps.as_ref(), 1).unwrap();
+        assert_eq!(proposal.collected_funds, Uint128(500));
+
+        // votes on rejected proposals are refunded on reveal
+        let msg = HandleMsg::RevealVote {
+            proposal_id: 2,
+            salt: Binary::from(b"salt4"),
+        };
+        let res = handle(deps.as_mut(), env.clone(), voter4, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("voter4"),
+                amount: vec![coin(200, "ucosm")],
+            })]
+        );
+
+        let trigger_msg = HandleMsg::TriggerDistribution {};
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            trigger_msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::RevealPeriodNotExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        env.block.height += 5;
+        let res = handle(deps.as_mut(), env.clone(), voter1, reveal(b"salt1"));
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::RevealPeriodExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // only revealed votes are matched, unrevealed funds are refunded
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            trigger_msg,
+        )
+        .unwrap();
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("fund_address"),
+                amount: vec![coin(1500u128, "ucosm")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("voter3"),
+                amount: vec![coin(50u128, "ucosm")],
+            }),
+        ];
+        assert_eq!(expected_msgs, res.messages);
+        assert!(res.attributes.contains(&attr("unrevealed_votes", 1)));
+
+        // next round needs a reveal period
+        let next_round_msg = HandleMsg::StartNextRound {
+            voting_period: Expiration::AtHeight(env.block.height + 15),
+            proposal_period: Expiration::AtHeight(env.block.height + 10),
+            reveal_period: None,
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            next_round_msg,
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::RevealPeriodRequired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // and it must end after voting
+        let next_round_msg = HandleMsg::StartNextRound {
+            voting_period: Expiration::AtHeight(env.block.height + 15),
+            proposal_period: Expiration::AtHeight(env.block.height + 10),
+            reveal_period: Some(Expiration::AtHeight(env.block.height + 15)),
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            next_round_msg,
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidRevealPeriod {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
     }
-}
 
-fn query_proposal_id(deps: Deps, id: u64) -> StdResult<Proposal> {
-    PROPOSALS.load(deps.storage, id.into())
-}
+    #[test]
+    fn delegate_funds() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
 
-fn query_all_proposals(deps: Deps) -> StdResult<AllProposalsResponse> {
-    let all: StdResult<Vec<(Vec<u8>, Proposal)>> = PROPOSALS
-        .range(deps.storage, None, None, Order::Ascending)
-        .collect();
-    all.map(|p| {
-        let res = p.into_iter().map(|x| x.1).collect();
+        let init_msg = mock_init_msg(&env);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
 
-        AllProposalsResponse { proposals: res }
-    })
-}
+        for fund_address in &["fund_address1", "fund_address2"] {
+            let msg = HandleMsg::CreateProposal {
+                title: String::from("test"),
+                description: String::from("test"),
+                metadata: None,
+                fund_address: HumanAddr::from(*fund_address),
+                recipients: None,
+            };
+            handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
 
-#[cfg(test)]
-mod tests {
-    use crate::contract::{handle, init, query_all_proposals, query_proposal_id};
-    use crate::error::ContractError;
-    use crate::matching::QuadraticFundingAlgorithm;
-    use crate::msg::{AllProposalsResponse, HandleMsg, InitMsg};
-    use crate::state::{Proposal, PROPOSALS};
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, BankMsg, Binary, CosmosMsg, HumanAddr};
-    use cw0::Expiration;
+        // delegations are topped up
+        let delegate = HandleMsg::DelegateFunds {
+            curator: HumanAddr::from("curator"),
+        };
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("delegator", &[coin(1000, "ucosm")]),
+            delegate.clone(),
+        )
+        .unwrap();
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("delegator", &[coin(500, "ucosm")]),
+            delegate.clone(),
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("delegated", 1500)));
 
-    #[test]
-    fn create_proposal() {
-        let mut env = mock_env();
-        let info = mock_info("addr", &[coin(1000, "ucosm")]);
-        let mut deps = mock_dependencies(&[]);
+        let res =
+            query_delegations_by_curator(deps.as_ref(), HumanAddr::from("curator"), None, None)
+                .unwrap();
+        assert_eq!(res.delegations.len(), 1);
+        assert_eq!(res.delegations[0].fund, coin(1500, "ucosm"));
 
-        let init_msg = InitMsg {
-            admin: HumanAddr::from("addr"),
-            leftover_addr: HumanAddr::from("addr"),
-            create_proposal_whitelist: None,
-            vote_proposal_whitelist: None,
-            voting_period: Expiration::AtHeight(env.block.height + 15),
-            proposal_period: Expiration::AtHeight(env.block.height + 10),
-            budget_denom: String::from("ucosm"),
-            algorithm: QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
-                parameter: "".to_string(),
-            },
+        let allocation = |proposal_id: u64, amount: u128| Allocation {
+            proposal_id,
+            amount: Uint128(amount),
         };
 
-        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
-        let msg = HandleMsg::CreateProposal {
-            title: String::from("test"),
-            description: String::from("test"),
-            metadata: Some(b"test".into()),
-            fund_address: HumanAddr::from("fund_address"),
+        // only the curator can allocate
+        let msg = HandleMsg::CuratorAllocate {
+            delegator: HumanAddr::from("delegator"),
+            allocations: vec![allocation(1, 100)],
         };
-
-        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-        // success case
+        let res = handle(deps.as_mut(), env.clone(), mock_info("other", &[]), msg);
         match res {
-            Ok(seq) => assert_eq!(seq.data.unwrap(), Binary::from(1_u64.to_be_bytes())),
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::DelegationNotFound {}) => {}
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
 
-        // proposal period expired
-        env.block.height = env.block.height + 1000;
-        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        let curator = mock_info("curator", &[]);
+        let msg = HandleMsg::CuratorAllocate {
+            delegator: HumanAddr::from("delegator"),
+            allocations: vec![allocation(1, 1000), allocation(2, 1000)],
+        };
+        let res = handle(deps.as_mut(), env.clone(), curator.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InsufficientDelegatedFunds {
+                available: 1500,
+                requested: 2000,
+            }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
 
+        let msg = HandleMsg::CuratorAllocate {
+            delegator: HumanAddr::from("delegator"),
+            allocations: vec![allocation(1, 600), allocation(2, 0)],
+        };
+        let res = handle(deps.as_mut(), env.clone(), curator.clone(), msg);
         match res {
             Ok(_) => panic!("expected error"),
-            Err(ContractError::ProposalPeriodExpired {}) => {}
+            Err(ContractError::ZeroAmount {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let msg = HandleMsg::CuratorAllocate {
+            delegator: HumanAddr::from("delegator"),
+            allocations: vec![allocation(1, u128::MAX), allocation(2, 1)],
+        };
+        let res = handle(deps.as_mut(), env.clone(), curator.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Overflow {}) => {}
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
 
-        // unauthorised
-        let env = mock_env();
-        let info = mock_info("true", &[coin(1000, "ucosm")]);
-        let mut deps = mock_dependencies(&[]);
-        let init_msg = InitMsg {
-            leftover_addr: HumanAddr::from("addr"),
-            admin: HumanAddr::from("person"),
-            create_proposal_whitelist: Some(vec![HumanAddr::from("false")]),
-            vote_proposal_whitelist: None,
-            voting_period: Default::default(),
-            proposal_period: Default::default(),
-            budget_denom: String::from("ucosm"),
-            algorithm: QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
-                parameter: "".to_string(),
-            },
+        // votes are attributed to the delegator
+        let msg = HandleMsg::CuratorAllocate {
+            delegator: HumanAddr::from("delegator"),
+            allocations: vec![allocation(1, 600), allocation(2, 400)],
         };
-        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
+        let res = handle(deps.as_mut(), env.clone(), curator.clone(), msg).unwrap();
+        assert!(res.attributes.contains(&attr("remaining", 500)));
+        let res =
+            query_votes_by_voter(deps.as_ref(), HumanAddr::from("delegator"), None, None).unwrap();
+        let curator_addr = deps.api.canonical_address(&curator.sender).unwrap();
+        let votes: Vec<(u64, Uint128, Option<CanonicalAddr>)> = res
+            .votes
+            .into_iter()
+            .map(|v| (v.proposal_id, v.fund.amount, v.curator))
+            .collect();
+        assert_eq!(
+            votes,
+            vec![
+                (1, Uint128(600), Some(curator_addr.clone())),
+                (2, Uint128(400), Some(curator_addr)),
+            ]
+        );
 
-        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        // delegator already voted on proposal through the curator
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("delegator", &[coin(100, "ucosm")]),
+            msg,
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::AddressAlreadyVotedProject {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
 
+        // revoked delegations are refunded
+        let delegator2 = mock_info("delegator2", &[coin(300, "ucosm")]);
+        handle(deps.as_mut(), env.clone(), delegator2.clone(), delegate).unwrap();
+        let revoke = HandleMsg::RevokeDelegation {
+            curator: HumanAddr::from("curator"),
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            delegator2.clone(),
+            revoke.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("delegator2"),
+                amount: vec![coin(300, "ucosm")],
+            })]
+        );
+        let res = handle(deps.as_mut(), env.clone(), delegator2, revoke.clone());
         match res {
             Ok(_) => panic!("expected error"),
-            Err(ContractError::Unauthorized {}) => {}
+            Err(ContractError::DelegationNotFound {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // can't revoke once voting is closed
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("delegator", &[]),
+            revoke,
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::VotingPeriodExpired {}) => {}
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
+
+        // unallocated funds are refunded on distribution
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        assert!(res.messages.contains(&CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: HumanAddr::from("delegator"),
+            amount: vec![coin(500, "ucosm")],
+        })));
+        assert!(res.attributes.contains(&attr("refunded_delegations", 1)));
     }
 
     #[test]
-    fn vote_proposal() {
-        let mut env = mock_env();
-        let info = mock_info("addr", &[coin(1000, "ucosm")]);
-        let mut deps = mock_dependencies(&[]);
+    fn relayed_vote() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
 
-        let mut init_msg = InitMsg {
-            leftover_addr: HumanAddr::from("addr"),
-            algorithm: QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
-                parameter: "".to_string(),
-            },
-            admin: HumanAddr::from("addr"),
-            create_proposal_whitelist: None,
-            vote_proposal_whitelist: None,
-            voting_period: Expiration::AtHeight(env.block.height + 15),
-            proposal_period: Expiration::AtHeight(env.block.height + 10),
-            budget_denom: String::from("ucosm"),
+        let key = SigningKey::from_bytes(&[1u8; 32]).unwrap();
+        let pubkey = Binary::from(key.verify_key().to_encoded_point(true).as_bytes());
+        let sign = |payload: &RelayedVotePayload| {
+            let payload = to_binary(payload).unwrap();
+            let signature: Signature = key.sign(payload.as_slice());
+            (payload, Binary::from(signature.as_ref()))
         };
-        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
 
-        let create_proposal_msg = HandleMsg::CreateProposal {
-            title: String::from("test"),
-            description: String::from("test"),
-            metadata: Some(Binary::from(b"test")),
-            fund_address: HumanAddr::from("fund_address"),
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        // voter's address is derived from the signing key
+        let signature: Signature = key.sign(b"");
+        let signer = verify_signature(b"", signature.as_ref(), &pubkey).unwrap();
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: SignerApi {
+                base: MockApi::default(),
+                addrs: vec![(HumanAddr::from("voter"), signer)]
+                    .into_iter()
+                    .collect(),
+            },
+            querier: MockQuerier::<Empty>::new(&[]),
         };
 
+        let init_msg = mock_init_msg(&env);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        for fund_address in &["fund_address1", "fund_address2"] {
+            let msg = HandleMsg::CreateProposal {
+                title: String::from("test"),
+                description: String::from("test"),
+                metadata: None,
+                fund_address: HumanAddr::from(*fund_address),
+                recipients: None,
+            };
+            handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // anyone can deposit for the voter
+        let msg = HandleMsg::DepositFunds {
+            voter: HumanAddr::from("voter"),
+        };
         let res = handle(
             deps.as_mut(),
             env.clone(),
-            info.clone(),
-            create_proposal_msg.clone(),
-        );
+            mock_info("sponsor", &[coin(1000, "ucosm")]),
+            msg,
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("deposit", 1000)));
+
+        let payload = RelayedVotePayload {
+            contract: env.contract.address.clone(),
+            proposal_id: 1,
+            amount: Uint128(600),
+            nonce: 0,
+            expiry: Expiration::AtHeight(env.block.height + 5),
+        };
+        let relay = |voter: &str, (payload, signature): (Binary, Binary)| HandleMsg::RelayedVote {
+            voter: HumanAddr::from(voter),
+            pubkey: pubkey.clone(),
+            payload,
+            signature,
+        };
+        let relayer = mock_info("relayer", &[]);
+
+        // vote is paid from the deposit and attributed to the voter
+        let msg = relay("voter", sign(&payload));
+        let res = handle(deps.as_mut(), env.clone(), relayer.clone(), msg.clone()).unwrap();
+        assert!(res.attributes.contains(&attr("collected_funds", 600)));
+        let res =
+            query_votes_by_voter(deps.as_ref(), HumanAddr::from("voter"), None, None).unwrap();
+        assert_eq!(res.votes.len(), 1);
+        assert_eq!(res.votes[0].fund, coin(600, "ucosm"));
+
+        // signed vote can't be replayed
+        let res = handle(deps.as_mut(), env.clone(), relayer.clone(), msg);
         match res {
-            Ok(seq) => assert_eq!(seq.data.unwrap(), Binary::from(1_u64.to_be_bytes())),
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidNonce {
+                expected: 1,
+                got: 0,
+            }) => {}
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
 
-        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
-        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-        // success case
+        let next = RelayedVotePayload {
+            proposal_id: 2,
+            amount: Uint128(500),
+            nonce: 1,
+            ..payload.clone()
+        };
+
+        // payload can't be changed after signing
+        let (_, signature) = sign(&next);
+        let tampered = RelayedVotePayload {
+            amount: Uint128(50),
+            ..next.clone()
+        };
+        let msg = relay("voter", (to_binary(&tampered).unwrap(), signature));
+        let res = handle(deps.as_mut(), env.clone(), relayer.clone(), msg);
         match res {
-            Ok(_) => {}
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidSignature {}) => {}
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
 
-        // double vote prevention
-        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        // signer must be the voter
+        let msg = relay("other", sign(&next));
+        let res = handle(deps.as_mut(), env.clone(), relayer.clone(), msg);
         match res {
             Ok(_) => panic!("expected error"),
-            Err(ContractError::AddressAlreadyVotedProject {}) => {}
+            Err(ContractError::PubkeyMismatch {}) => {}
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
 
-        // whitelist check
-        let mut deps = mock_dependencies(&[]);
-        init_msg.vote_proposal_whitelist = Some(vec![HumanAddr::from("admin")]);
-        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
-        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        let msg = relay("voter", sign(&next));
+        let res = handle(deps.as_mut(), env.clone(), relayer.clone(), msg);
         match res {
             Ok(_) => panic!("expected error"),
-            Err(ContractError::Unauthorized {}) => {}
+            Err(ContractError::InsufficientDeposit {
+                available: 400,
+                requested: 500,
+            }) => {}
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
 
-        // proposal period expired
-        let mut deps = mock_dependencies(&[]);
-        init_msg.vote_proposal_whitelist = None;
-        init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
-        env.block.height = env.block.height + 15;
-        let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        let zero = RelayedVotePayload {
+            amount: Uint128::zero(),
+            ..next.clone()
+        };
+        let msg = relay("voter", sign(&zero));
+        let res = handle(deps.as_mut(), env.clone(), relayer.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ZeroAmount {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let expired = RelayedVotePayload {
+            expiry: Expiration::AtHeight(env.block.height),
+            ..next.clone()
+        };
+        let msg = relay("voter", sign(&expired));
+        let res = handle(deps.as_mut(), env.clone(), relayer.clone(), msg);
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::SignatureExpired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
 
+        // payload signed for another round
+        let other_round = RelayedVotePayload {
+            contract: HumanAddr::from("other_round"),
+            ..next
+        };
+        let msg = relay("voter", sign(&other_round));
+        let res = handle(deps.as_mut(), env.clone(), relayer, msg);
         match res {
             Ok(_) => panic!("expected error"),
-            Err(ContractError::VotingPeriodExpired {}) => {}
+            Err(ContractError::Unauthorized {}) => {}
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
+
+        let res = query_relay_account(deps.as_ref(), HumanAddr::from("voter")).unwrap();
+        assert_eq!(
+            res,
+            RelayAccountResponse {
+                deposit: Some(coin(400, "ucosm")),
+                nonce: 1,
+            }
+        );
+
+        // unused deposit is refunded on distribution
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        assert!(res.messages.contains(&CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: HumanAddr::from("voter"),
+            amount: vec![coin(400, "ucosm")],
+        })));
+        assert!(res.attributes.contains(&attr("refunded_deposits", 1)));
+    }
+
+    #[test]
+    fn single_proposal_distribution() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        init(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            mock_init_msg(&env),
+        )
+        .unwrap();
+
+        let msg = HandleMsg::CreateProposal {
+            title: String::from("proposal 1"),
+            description: "".to_string(),
+            metadata: None,
+            fund_address: HumanAddr::from("fund_address1"),
+            recipients: None,
+        };
+        handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = HandleMsg::VoteProposal { proposal_id: 1 };
+        handle(
+            deps.as_mut(),
+            env,
+            mock_info("address1", &[coin(500, "ucosm")]),
+            msg,
+        )
+        .unwrap();
+
+        // the whole budget is matched, so no zero leftover is sent
+        let mut env = mock_env();
+        env.block.height += 1000;
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            HandleMsg::TriggerDistribution {},
+        )
+        .unwrap();
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: HumanAddr::from("fund_address1"),
+            amount: vec![coin(1500u128, "ucosm")],
+        })];
+        assert_eq!(expected_msgs, res.messages);
+        assert!(res.attributes.contains(&attr("leftover", 0)));
     }
 
     #[test]
@@ -445,18 +4891,7 @@ mod tests {
         let info = mock_info("admin", &[coin(budget, "ucosm")]);
         let mut deps = mock_dependencies(&[]);
 
-        let init_msg = InitMsg {
-            leftover_addr: HumanAddr::from("addr"),
-            algorithm: QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
-                parameter: "".to_string(),
-            },
-            admin: HumanAddr::from("admin"),
-            create_proposal_whitelist: None,
-            vote_proposal_whitelist: None,
-            voting_period: Expiration::AtHeight(env.block.height + 15),
-            proposal_period: Expiration::AtHeight(env.block.height + 10),
-            budget_denom: String::from("ucosm"),
-        };
+        let init_msg = mock_init_msg(&env);
 
         init(deps.as_mut(), env.clone(), info.clone(), init_msg.clone()).unwrap();
 
@@ -466,6 +4901,7 @@ mod tests {
             description: "".to_string(),
             metadata: Some(Binary::from(b"test")),
             fund_address: HumanAddr::from("fund_address1"),
+            recipients: None,
         };
         let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
         match res {
@@ -478,6 +4914,7 @@ mod tests {
             description: "".to_string(),
             metadata: Some(Binary::from(b"test")),
             fund_address: HumanAddr::from("fund_address2"),
+            recipients: None,
         };
         let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
         match res {
@@ -490,6 +4927,7 @@ mod tests {
             description: "".to_string(),
             metadata: Some(Binary::from(b"test")),
             fund_address: HumanAddr::from("fund_address3"),
+            recipients: None,
         };
         let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
         match res {
@@ -501,6 +4939,7 @@ mod tests {
             description: "".to_string(),
             metadata: Some(Binary::from(b"test")),
             fund_address: HumanAddr::from("fund_address4"),
+            recipients: None,
         };
         let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
         match res {
@@ -570,6 +5009,9 @@ mod tests {
         handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
         let proposal4 = vote41_fund + vote42_fund;
 
+        // no result before distribution
+        assert!(query_distribution_result(deps.as_ref(), None).is_err());
+
         let trigger_msg = HandleMsg::TriggerDistribution {};
         let info = mock_info("admin", &[]);
         let mut env = mock_env();
@@ -625,7 +5067,73 @@ mod tests {
             .sum();
         let total_fund = proposal1 + proposal2 + proposal3 + proposal4 + budget;
 
-        assert_eq!(total_fund, expected_msg_total_distr)
+        assert_eq!(total_fund, expected_msg_total_distr);
+
+        // result is kept after payout
+        let result = query_distribution_result(deps.as_ref(), None).unwrap();
+        assert_eq!(result.round, 1);
+        assert_eq!(result.height, env.block.height);
+        assert_eq!(result.leftover, coin(1u128, "ucosm"));
+        assert_eq!(
+            result.algorithm,
+            QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
+                parameter: "".to_string(),
+            }
+        );
+        let ids: Vec<u64> = result.grants.iter().map(|g| g.proposal_id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        let grants: u128 = result.grants.iter().map(|g| g.grant.u128()).sum();
+        assert_eq!(grants + 1, budget);
+        assert_eq!(
+            result,
+            query_distribution_result(deps.as_ref(), Some(1)).unwrap()
+        );
+
+        // match breakdown agrees with distributed grants
+        for g in result.grants.iter() {
+            let breakdown = query_proposal_match_breakdown(deps.as_ref(), g.proposal_id).unwrap();
+            assert_eq!(breakdown.grant, g.grant);
+            assert_eq!(breakdown.budget, Uint128(budget));
+        }
+    }
+
+    #[test]
+    fn query_voter_votes() {
+        let env = mock_env();
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = mock_init_msg(&env);
+        init(deps.as_mut(), env.clone(), info.clone(), init_msg).unwrap();
+
+        for i in 1..=2 {
+            let msg = HandleMsg::CreateProposal {
+                title: format!("proposal {}", i),
+                description: "".to_string(),
+                metadata: None,
+                fund_address: HumanAddr::from(format!("fund_address{}", i)),
+                recipients: None,
+            };
+            handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let voter = mock_info("voter", &[coin(100 * i as u128, "ucosm")]);
+            let msg = HandleMsg::VoteProposal { proposal_id: i };
+            handle(deps.as_mut(), env.clone(), voter, msg).unwrap();
+        }
+
+        let voter = HumanAddr::from("voter");
+        let res = query_votes_by_voter(deps.as_ref(), voter.clone(), None, Some(1)).unwrap();
+        assert_eq!(res.votes.len(), 1);
+        assert_eq!(res.votes[0].proposal_id, 1);
+        assert_eq!(res.votes[0].fund, coin(100, "ucosm"));
+
+        let res = query_votes_by_voter(deps.as_ref(), voter, Some(1), None).unwrap();
+        assert_eq!(res.votes.len(), 1);
+        assert_eq!(res.votes[0].proposal_id, 2);
+        assert_eq!(res.votes[0].fund, coin(200, "ucosm"));
+
+        let res =
+            query_votes_by_voter(deps.as_ref(), HumanAddr::from("other"), None, None).unwrap();
+        assert!(res.votes.is_empty());
     }
 
     #[test]
@@ -672,7 +5180,7 @@ mod tests {
             ..Default::default()
         };
         let _ = PROPOSALS.save(&mut deps.storage, 2_u64.into(), &proposal1);
-        let res = query_all_proposals(deps.as_ref()).unwrap();
+        let res = query_all_proposals(deps.as_ref(), None).unwrap();
 
         assert_eq!(
             AllProposalsResponse {
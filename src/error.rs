@@ -9,6 +9,18 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("No pending admin")]
+    NoPendingAdmin {},
+
+    #[error("Whitelist and cw4 group can't be used together")]
+    WhitelistAndGroupConflict {},
+
+    #[error("Whitelist is not enabled")]
+    WhitelistNotEnabled {},
+
+    #[error("Cw4 voter weights require a voter group")]
+    VoterGroupRequired {},
+
     #[error("Proposal not found")]
     ProposalNotFound {},
 
@@ -27,9 +39,138 @@ pub enum ContractError {
     #[error("Wrong fund coin (expected: {expected}, got: {got})")]
     WrongFundCoin { expected: String, got: String },
 
+    #[error("Proposal rejected")]
+    ProposalRejected {},
+
+    #[error("Proposal has votes")]
+    ProposalHasVotes {},
+
+    #[error("Wrong proposal deposit (expected: {expected}, got: {got})")]
+    WrongProposalDeposit { expected: String, got: String },
+
+    #[error("Proposal deposit can't be zero")]
+    ZeroProposalDeposit {},
+
+    #[error("Voting already started")]
+    VotingStarted {},
+
+    #[error("Contribution too low (min: {min}, got: {got})")]
+    ContributionTooLow { min: u128, got: u128 },
+
     #[error("Address already voted project")]
     AddressAlreadyVotedProject {},
 
     #[error("CLR algorithm requires a budget constrain")]
     CLRConstrainRequired {},
+
+    #[error("Invalid length limit (min: {min}, max: {max})")]
+    InvalidLengthLimit { min: u64, max: u64 },
+
+    #[error("Proposal title is empty")]
+    EmptyTitle {},
+
+    #[error("Title length out of bounds (min: {min}, max: {max}, got: {got})")]
+    TitleLengthOutOfBounds { min: u64, max: u64, got: u64 },
+
+    #[error("Description length out of bounds (min: {min}, max: {max}, got: {got})")]
+    DescriptionLengthOutOfBounds { min: u64, max: u64, got: u64 },
+
+    #[error("Metadata length out of bounds (min: {min}, max: {max}, got: {got})")]
+    MetadataLengthOutOfBounds { min: u64, max: u64, got: u64 },
+
+    #[error("Fund address cannot be the contract address")]
+    FundAddressIsContract {},
+
+    #[error("Recipient shares must sum up to 10000 basis points (got: {total})")]
+    InvalidRecipientShares { total: u64 },
+
+    #[error("Too many recipients (max: {max}, got: {got})")]
+    TooManyRecipients { max: u64, got: u64 },
+
+    #[error("Recipient share is zero")]
+    ZeroRecipientShare {},
+
+    #[error("Duplicate recipient: {addr}")]
+    DuplicateRecipient { addr: String },
+
+    #[error("Denom not accepted: {denom}")]
+    DenomNotAccepted { denom: String },
+
+    #[error("Invalid rate for denom: {denom}")]
+    InvalidDenomRate { denom: String },
+
+    #[error("Commit reveal voting is not enabled")]
+    CommitRevealDisabled {},
+
+    #[error("Votes must be committed and revealed")]
+    CommitRevealEnabled {},
+
+    #[error("Commitment already exists")]
+    DuplicateCommitment {},
+
+    #[error("Commitment not found")]
+    CommitmentNotFound {},
+
+    #[error("Reveal period expired")]
+    RevealPeriodExpired {},
+
+    #[error("Reveal period not expired")]
+    RevealPeriodNotExpired {},
+
+    #[error("Reveal period is required in commit reveal rounds")]
+    RevealPeriodRequired {},
+
+    #[error("Reveal period must end after voting period")]
+    InvalidRevealPeriod {},
+
+    #[error("Delegation not found")]
+    DelegationNotFound {},
+
+    #[error("Insufficient delegated funds (available: {available}, requested: {requested})")]
+    InsufficientDelegatedFunds { available: u128, requested: u128 },
+
+    #[error("Insufficient deposit (available: {available}, requested: {requested})")]
+    InsufficientDeposit { available: u128, requested: u128 },
+
+    #[error("Amount can't be zero")]
+    ZeroAmount {},
+
+    #[error("Amount overflow")]
+    Overflow {},
+
+    #[error("Voter score too high (max: {max}, got: {got})")]
+    VoterScoreTooHigh { max: u64, got: u64 },
+
+    #[error("Invalid signature")]
+    InvalidSignature {},
+
+    #[error("Public key doesn't belong to voter")]
+    PubkeyMismatch {},
+
+    #[error("Signed vote expired")]
+    SignatureExpired {},
+
+    #[error("Invalid nonce (expected: {expected}, got: {got})")]
+    InvalidNonce { expected: u64, got: u64 },
+
+    #[error("Round is already distributed")]
+    AlreadyDistributed {},
+
+    #[error("Round is not distributed yet")]
+    RoundNotDistributed {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Admin can't be renounced while leftover is rolled over")]
+    RolloverPending {},
+
+    #[error("Cannot migrate from different contract type: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from newer version ({previous_version}) to older ({new_version})")]
+    CannotMigrateVersion {
+        previous_version: String,
+        new_version: String,
+    },
 }
@@ -0,0 +1,598 @@
+use cosmwasm_std::{
+    attr, to_binary, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env, HandleResponse,
+    HumanAddr, InitResponse, MessageInfo, Order, StdResult, WasmMsg,
+};
+
+use crate::factory::error::ContractError;
+use crate::factory::msg::{HandleMsg, InitMsg, QueryMsg, RoundStatus, RoundsResponse};
+use crate::factory::state::{round_seq, Config, Round, CONFIG, PENDING_ROUND, ROUNDS};
+use crate::msg::{InitMsg as RoundInitMsg, QueryMsg as RoundQueryMsg};
+use cosmwasm_storage::nextval;
+use cw_storage_plus::Bound;
+
+pub fn init(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InitMsg,
+) -> Result<InitResponse, ContractError> {
+    let cfg = Config {
+        admin: deps.api.canonical_address(&msg.admin)?,
+        round_code_id: msg.round_code_id,
+    };
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: HandleMsg,
+) -> Result<HandleResponse, ContractError> {
+    match msg {
+        HandleMsg::CreateRound {
+            label,
+            description,
+            init_msg,
+        } => handle_create_round(deps, env, info, label, description, init_msg),
+        HandleMsg::RegisterRound {} => handle_register_round(deps, env, info),
+        HandleMsg::ConfirmRound {} => handle_confirm_round(deps, env, info),
+        HandleMsg::UpdateConfig {
+            admin,
+            round_code_id,
+        } => handle_update_config(deps, env, info, admin, round_code_id),
+    }
+}
+
+pub fn handle_create_round(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    label: String,
+    description: String,
+    mut init_msg: Box<RoundInitMsg>,
+) -> Result<HandleResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only admin can create rounds
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let id = nextval(&mut round_seq(deps.storage))?;
+    let round = Round {
+        id,
+        address: CanonicalAddr::default(),
+        label: label.clone(),
+        description,
+        creator: sender,
+        created_height: env.block.height,
+    };
+    PENDING_ROUND.save(deps.storage, &round)?;
+
+    // round registers its address on init, confirm fails the transaction if it didn't
+    init_msg.factory = Some(env.contract.address.clone());
+    let messages = vec![
+        CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: config.round_code_id,
+            msg: to_binary(&init_msg)?,
+            send: info.sent_funds,
+            label: Some(label.clone()),
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address,
+            msg: to_binary(&HandleMsg::ConfirmRound {})?,
+            send: vec![],
+        }),
+    ];
+
+    Ok(HandleResponse {
+        messages,
+        attributes: vec![
+            attr("action", "create_round"),
+            attr("sender", &info.sender),
+            attr("label", label),
+            attr("round_id", id),
+        ],
+        data: Some(Binary::from(id.to_be_bytes())),
+    })
+}
+
+pub fn handle_register_round(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let mut round = PENDING_ROUND
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingRound {})?;
+
+    round.address = deps.api.canonical_address(&info.sender)?;
+    ROUNDS.save(deps.storage, round.id.into(), &round)?;
+    PENDING_ROUND.remove(deps.storage);
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "register_round"),
+            attr("round_id", round.id),
+            attr("address", info.sender),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn handle_confirm_round(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    // only factory itself can confirm
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if PENDING_ROUND.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::RoundNotRegistered {});
+    }
+
+    Ok(HandleResponse {
+        attributes: vec![attr("action", "confirm_round")],
+        ..Default::default()
+    })
+}
+
+pub fn handle_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    admin: Option<HumanAddr>,
+    round_code_id: Option<u64>,
+) -> Result<HandleResponse, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // only admin can update config
+    if deps.api.canonical_address(&info.sender)? != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut attributes = vec![
+        attr("action", "update_config"),
+        attr("sender", &info.sender),
+    ];
+
+    if let Some(admin) = admin {
+        config.admin = deps.api.canonical_address(&admin)?;
+        attributes.push(attr("admin", admin));
+    }
+    if let Some(round_code_id) = round_code_id {
+        config.round_code_id = round_code_id;
+        attributes.push(attr("round_code_id", round_code_id));
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes,
+        ..Default::default()
+    })
+}
+
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Round { id } => to_binary(&query_round(deps, id)?),
+        QueryMsg::ActiveRounds { start_after, limit } => {
+            to_binary(&query_rounds(deps, false, start_after, limit)?)
+        }
+        QueryMsg::FinishedRounds { start_after, limit } => {
+            to_binary(&query_rounds(deps, true, start_after, limit)?)
+        }
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<Config> {
+    CONFIG.load(deps.storage)
+}
+
+fn query_round(deps: Deps, id: u64) -> StdResult<Round> {
+    ROUNDS.load(deps.storage, id.into())
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+// rounds queried per page, limit only counts matching rounds
+const MAX_SCAN: usize = 100;
+
+// round status is read from the round contract, finished rounds are distributed
+fn query_rounds(
+    deps: Deps,
+    distributed: bool,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RoundsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let mut rounds = vec![];
+    let mut last_scanned = None;
+    for item in ROUNDS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(MAX_SCAN)
+    {
+        let (_, round) = item?;
+        last_scanned = Some(round.id);
+        let status: RoundStatus = deps.querier.query_wasm_smart(
+            deps.api.human_address(&round.address)?,
+            &RoundQueryMsg::Config {},
+        )?;
+        if status.distributed == distributed {
+            rounds.push(round);
+        }
+        if rounds.len() == limit {
+            break;
+        }
+    }
+
+    Ok(RoundsResponse {
+        rounds,
+        last_scanned,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::{handle as round_handle, init as round_init, query as round_query};
+    use crate::factory::contract::{
+        handle, init, query_config, query_round, query_rounds, MAX_SCAN,
+    };
+    use crate::factory::error::ContractError;
+    use crate::factory::msg::{HandleMsg, InitMsg};
+    use crate::factory::state::{Round, ROUNDS};
+    use crate::msg::{HandleMsg as RoundHandleMsg, QueryMsg as RoundQueryMsg};
+    use crate::testing::mock_init_msg;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        coin, from_slice, to_binary, Api, Binary, ContractResult, CosmosMsg, Empty, HumanAddr,
+        OwnedDeps, Querier, QuerierResult, QueryRequest, SystemResult, WasmMsg, WasmQuery,
+    };
+    use std::collections::HashMap;
+
+    // mock querier answering config queries of the given rounds
+    struct RoundQuerier {
+        base: MockQuerier,
+        configs: HashMap<HumanAddr, Binary>,
+    }
+
+    impl Querier for RoundQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            match request {
+                QueryRequest::Wasm(WasmQuery::Smart { contract_addr, .. }) => {
+                    SystemResult::Ok(ContractResult::Ok(self.configs[&contract_addr].clone()))
+                }
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    // config query response of a round contract
+    fn round_config(distributed: bool) -> Binary {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("admin", &[coin(1000, "ucosm")]);
+        round_init(deps.as_mut(), mock_env(), info, mock_init_msg(&mock_env())).unwrap();
+        if distributed {
+            let mut env = mock_env();
+            env.block.height += 1000;
+            let msg = RoundHandleMsg::TriggerDistribution {};
+            round_handle(deps.as_mut(), env, mock_info("admin", &[]), msg).unwrap();
+        }
+        round_query(deps.as_ref(), mock_env(), RoundQueryMsg::Config {}).unwrap()
+    }
+
+    fn mock_dependencies_with_rounds(
+        rounds: &[(&str, bool)],
+    ) -> OwnedDeps<MockStorage, MockApi, RoundQuerier> {
+        let configs = rounds
+            .iter()
+            .map(|(addr, distributed)| (HumanAddr::from(*addr), round_config(*distributed)))
+            .collect();
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: RoundQuerier {
+                base: MockQuerier::new(&[]),
+                configs,
+            },
+        }
+    }
+
+    #[test]
+    fn create_round() {
+        let env = mock_env();
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            admin: HumanAddr::from("admin"),
+            round_code_id: 7,
+        };
+        init(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            init_msg,
+        )
+        .unwrap();
+
+        let msg = HandleMsg::CreateRound {
+            label: String::from("round"),
+            description: String::from("first round"),
+            init_msg: Box::new(mock_init_msg(&mock_env())),
+        };
+
+        // only admin can create rounds
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[coin(1000, "ucosm")]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // round is instantiated with factory set and budget forwarded
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[coin(1000, "ucosm")]),
+            msg,
+        )
+        .unwrap();
+        let mut round_msg = mock_init_msg(&mock_env());
+        round_msg.factory = Some(env.contract.address.clone());
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                code_id: 7,
+                msg: to_binary(&round_msg).unwrap(),
+                send: vec![coin(1000, "ucosm")],
+                label: Some(String::from("round")),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.clone(),
+                msg: to_binary(&HandleMsg::ConfirmRound {}).unwrap(),
+                send: vec![],
+            }),
+        ];
+        assert_eq!(expected_msgs, res.messages);
+
+        // confirm fails until round registers
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(env.contract.address.clone(), &[]),
+            HandleMsg::ConfirmRound {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::RoundNotRegistered {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("round1", &[]),
+            HandleMsg::RegisterRound {},
+        )
+        .unwrap();
+        handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(env.contract.address.clone(), &[]),
+            HandleMsg::ConfirmRound {},
+        )
+        .unwrap();
+
+        let round = query_round(deps.as_ref(), 1).unwrap();
+        assert_eq!(
+            deps.api.human_address(&round.address).unwrap(),
+            HumanAddr::from("round1")
+        );
+        assert_eq!(round.label, "round");
+        assert_eq!(round.created_height, env.block.height);
+
+        // nothing left to register
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            HandleMsg::RegisterRound {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::NoPendingRound {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        // only factory can confirm
+        let res = handle(
+            deps.as_mut(),
+            env,
+            mock_info("other", &[]),
+            HandleMsg::ConfirmRound {},
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn round_registers_with_factory() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("factory", &[coin(1000, "ucosm")]);
+        let mut msg = mock_init_msg(&mock_env());
+        msg.factory = Some(HumanAddr::from("factory"));
+
+        let res = round_init(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let expected_msgs: Vec<CosmosMsg<_>> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("factory"),
+            msg: to_binary(&HandleMsg::RegisterRound {}).unwrap(),
+            send: vec![],
+        })];
+        assert_eq!(expected_msgs, res.messages);
+    }
+
+    #[test]
+    fn update_config() {
+        let env = mock_env();
+        let mut deps = mock_dependencies(&[]);
+
+        let init_msg = InitMsg {
+            admin: HumanAddr::from("admin"),
+            round_code_id: 7,
+        };
+        init(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            init_msg,
+        )
+        .unwrap();
+
+        let msg = HandleMsg::UpdateConfig {
+            admin: Some(HumanAddr::from("new_admin")),
+            round_code_id: Some(8),
+        };
+        let res = handle(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            msg.clone(),
+        );
+        match res {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Unauthorized {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        handle(deps.as_mut(), env, mock_info("admin", &[]), msg).unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(
+            deps.api.human_address(&config.admin).unwrap(),
+            HumanAddr::from("new_admin")
+        );
+        assert_eq!(config.round_code_id, 8);
+    }
+
+    #[test]
+    fn query_active_and_finished_rounds() {
+        let env = mock_env();
+        let mut deps = mock_dependencies_with_rounds(&[
+            ("round1", false),
+            ("round2", true),
+            ("round3", false),
+        ]);
+
+        let init_msg = InitMsg {
+            admin: HumanAddr::from("admin"),
+            round_code_id: 7,
+        };
+        init(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            init_msg,
+        )
+        .unwrap();
+
+        for i in 1..4 {
+            let msg = HandleMsg::CreateRound {
+                label: format!("round{}", i),
+                description: String::from(""),
+                init_msg: Box::new(mock_init_msg(&mock_env())),
+            };
+            handle(deps.as_mut(), env.clone(), mock_info("admin", &[]), msg).unwrap();
+            let round = mock_info(format!("round{}", i), &[]);
+            handle(
+                deps.as_mut(),
+                env.clone(),
+                round,
+                HandleMsg::RegisterRound {},
+            )
+            .unwrap();
+        }
+
+        let active = query_rounds(deps.as_ref(), false, None, None).unwrap();
+        let ids: Vec<u64> = active.rounds.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+
+        let finished = query_rounds(deps.as_ref(), true, None, None).unwrap();
+        let ids: Vec<u64> = finished.rounds.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![2]);
+
+        // pagination
+        let active = query_rounds(deps.as_ref(), false, None, Some(1)).unwrap();
+        let ids: Vec<u64> = active.rounds.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![1]);
+        let active = query_rounds(deps.as_ref(), false, Some(1), Some(1)).unwrap();
+        let ids: Vec<u64> = active.rounds.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![3]);
+    }
+
+    #[test]
+    fn query_rounds_scan_limit() {
+        // rounds only need to report their status, all but the last are finished
+        let count = MAX_SCAN as u64 + 1;
+        let configs = (1..=count)
+            .map(|i| {
+                let status = format!(r#"{{"distributed":{}}}"#, i < count);
+                (
+                    HumanAddr::from(format!("round{}", i)),
+                    Binary::from(status.as_bytes()),
+                )
+            })
+            .collect();
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: RoundQuerier {
+                base: MockQuerier::new(&[]),
+                configs,
+            },
+        };
+        for i in 1..=count {
+            let round = Round {
+                id: i,
+                address: deps
+                    .api
+                    .canonical_address(&HumanAddr::from(format!("round{}", i)))
+                    .unwrap(),
+                label: format!("round{}", i),
+                description: String::from(""),
+                creator: deps.api.canonical_address(&"admin".into()).unwrap(),
+                created_height: 1,
+            };
+            ROUNDS.save(&mut deps.storage, i.into(), &round).unwrap();
+        }
+
+        // scan stops before finding the active round
+        let active = query_rounds(deps.as_ref(), false, None, None).unwrap();
+        assert!(active.rounds.is_empty());
+        assert_eq!(active.last_scanned, Some(MAX_SCAN as u64));
+
+        let active = query_rounds(deps.as_ref(), false, active.last_scanned, None).unwrap();
+        let ids: Vec<u64> = active.rounds.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![count]);
+        assert_eq!(active.last_scanned, Some(count));
+    }
+}
@@ -0,0 +1,20 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("No round is waiting for registration")]
+    NoPendingRound {},
+
+    #[error("Round contract did not register with the factory")]
+    RoundNotRegistered {},
+
+    #[error("Round not found")]
+    RoundNotFound {},
+}
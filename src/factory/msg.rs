@@ -0,0 +1,63 @@
+use crate::factory::state::Round;
+use crate::msg::InitMsg as RoundInitMsg;
+use cosmwasm_std::HumanAddr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitMsg {
+    pub admin: HumanAddr,
+    pub round_code_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    // instantiates a round contract, sent funds are forwarded as the round budget
+    CreateRound {
+        label: String,
+        description: String,
+        init_msg: Box<RoundInitMsg>,
+    },
+    // sent by the round contract on init
+    RegisterRound {},
+    // sent by the factory itself to check the round was registered
+    ConfirmRound {},
+    UpdateConfig {
+        admin: Option<HumanAddr>,
+        round_code_id: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Round {
+        id: u64,
+    },
+    // rounds that are not distributed yet
+    ActiveRounds {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // rounds that are distributed
+    FinishedRounds {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundsResponse {
+    pub rounds: Vec<Round>,
+    // last round scanned, pass as start_after to continue
+    pub last_scanned: Option<u64>,
+}
+
+// round config fields read by the factory, other fields are ignored
+// so round config changes don't break round queries
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundStatus {
+    pub distributed: bool,
+}
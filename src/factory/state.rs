@@ -0,0 +1,38 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, Storage};
+use cosmwasm_storage::{singleton, Singleton};
+use cw_storage_plus::{Item, Map, U64Key};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    // admin creates rounds and updates config
+    pub admin: CanonicalAddr,
+    // code id of the round contract instantiated by the factory
+    pub round_code_id: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Round {
+    pub id: u64,
+    // set once the round contract registers itself
+    pub address: CanonicalAddr,
+    pub label: String,
+    pub description: String,
+    pub creator: CanonicalAddr,
+    pub created_height: u64,
+}
+
+pub const ROUNDS: Map<U64Key, Round> = Map::new("rounds");
+
+// round instantiated in the current transaction, waiting for its address
+pub const PENDING_ROUND: Item<Round> = Item::new("pending_round");
+
+pub const ROUND_SEQ: &[u8] = b"round_seq";
+
+pub fn round_seq(storage: &mut dyn Storage) -> Singleton<'_, u64> {
+    singleton(storage, ROUND_SEQ)
+}
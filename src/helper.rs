@@ -1,11 +1,27 @@
 use crate::error::ContractError;
-use cosmwasm_std::Coin;
+use crate::msg::{DenomRate, RecipientShare};
+use crate::state::{Config, PausedAt, Recipient, BASIS_POINTS};
+use cosmwasm_std::{Binary, BlockInfo, CanonicalAddr, Coin, Env, HumanAddr};
+use cw0::Expiration;
+use k256::ecdsa::signature::Verifier;
+use k256::ecdsa::{Signature, VerifyingKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 
-// extract budget coin validate against sent_funds.denom
-pub fn extract_budget_coin(sent_funds: &[Coin], denom: &str) -> Result<Coin, ContractError> {
+// extract single coin sent
+pub fn extract_coin(sent_funds: &[Coin]) -> Result<Coin, ContractError> {
     if sent_funds.len() != 1 {
         return Err(ContractError::WrongCoinSent {});
     }
+    Ok(sent_funds[0].clone())
+}
+
+// extract budget coin validate against sent_funds.denom
+pub fn extract_budget_coin(sent_funds: &[Coin], denom: &str) -> Result<Coin, ContractError> {
+    extract_coin(sent_funds)?;
     if sent_funds[0].denom != *denom {
         return Err(ContractError::WrongFundCoin {
             expected: denom.to_string(),
@@ -15,12 +31,175 @@ pub fn extract_budget_coin(sent_funds: &[Coin], denom: &str) -> Result<Coin, Con
     Ok(sent_funds[0].clone())
 }
 
+// validate proposal fields against configured limits
+pub fn validate_proposal_fields(
+    config: &Config,
+    env: &Env,
+    title: &str,
+    description: &str,
+    metadata: &Option<Binary>,
+    fund_address: &HumanAddr,
+) -> Result<(), ContractError> {
+    if title.trim().is_empty() {
+        return Err(ContractError::EmptyTitle {});
+    }
+    if !config.title_length.contains(title.len()) {
+        return Err(ContractError::TitleLengthOutOfBounds {
+            min: config.title_length.min,
+            max: config.title_length.max,
+            got: title.len() as u64,
+        });
+    }
+    if !config.description_length.contains(description.len()) {
+        return Err(ContractError::DescriptionLengthOutOfBounds {
+            min: config.description_length.min,
+            max: config.description_length.max,
+            got: description.len() as u64,
+        });
+    }
+    let metadata_len = metadata.as_ref().map_or(0, |m| m.len());
+    if !config.metadata_length.contains(metadata_len) {
+        return Err(ContractError::MetadataLengthOutOfBounds {
+            min: config.metadata_length.min,
+            max: config.metadata_length.max,
+            got: metadata_len as u64,
+        });
+    }
+    if *fund_address == env.contract.address {
+        return Err(ContractError::FundAddressIsContract {});
+    }
+    Ok(())
+}
+
+// validate recipients are at most max_recipients, shares are non zero, unique and
+// sum up to BASIS_POINTS
+pub fn validate_recipients(
+    env: &Env,
+    recipients: &[RecipientShare],
+    max_recipients: u64,
+) -> Result<(), ContractError> {
+    if recipients.len() as u64 > max_recipients {
+        return Err(ContractError::TooManyRecipients {
+            max: max_recipients,
+            got: recipients.len() as u64,
+        });
+    }
+    let mut total: u64 = 0;
+    for (i, r) in recipients.iter().enumerate() {
+        if r.share == 0 {
+            return Err(ContractError::ZeroRecipientShare {});
+        }
+        if r.addr == env.contract.address {
+            return Err(ContractError::FundAddressIsContract {});
+        }
+        if recipients[..i].iter().any(|o| o.addr == r.addr) {
+            return Err(ContractError::DuplicateRecipient {
+                addr: r.addr.to_string(),
+            });
+        }
+        total = total.saturating_add(r.share);
+    }
+    if total != BASIS_POINTS {
+        return Err(ContractError::InvalidRecipientShares { total });
+    }
+    Ok(())
+}
+
+// validate rates are non zero and not set for budget denom
+pub fn validate_denom_rates(budget_denom: &str, rates: &[DenomRate]) -> Result<(), ContractError> {
+    for r in rates {
+        if r.denom == budget_denom || r.rate.is_zero() {
+            return Err(ContractError::InvalidDenomRate {
+                denom: r.denom.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// check reveal period ends after voting period, height and time periods can't be compared
+pub fn validate_reveal_period(
+    reveal_period: &Expiration,
+    voting_period: &Expiration,
+) -> Result<(), ContractError> {
+    match reveal_period.partial_cmp(voting_period) {
+        Some(Ordering::Greater) => Ok(()),
+        _ => Err(ContractError::InvalidRevealPeriod {}),
+    }
+}
+
+// pushes a period still open when paused back by the paused duration
+pub fn extend_period(period: &Expiration, paused_at: &PausedAt, block: &BlockInfo) -> Expiration {
+    match *period {
+        Expiration::AtHeight(height) if height > paused_at.height => {
+            Expiration::AtHeight(height + (block.height - paused_at.height))
+        }
+        Expiration::AtTime(time) if time > paused_at.time => {
+            Expiration::AtTime(time + (block.time - paused_at.time))
+        }
+        period => period,
+    }
+}
+
+// add coin to coins, merging amounts of the same denom
+pub fn add_coin(coins: &mut Vec<Coin>, c: &Coin) {
+    match coins.iter_mut().find(|o| o.denom == c.denom) {
+        Some(o) => o.amount += c.amount,
+        None => coins.push(c.clone()),
+    }
+}
+
+// hash of a committed vote, binds it to the voter
+pub fn commitment_hash(voter: &HumanAddr, proposal_id: u64, salt: &[u8]) -> Binary {
+    let mut hasher = Sha256::new();
+    hasher.update(voter.as_bytes());
+    hasher.update(proposal_id.to_be_bytes());
+    hasher.update(salt);
+    Binary::from(&hasher.finalize()[..])
+}
+
+// verifies secp256k1 signature over sha256(message), returns signer's account address
+pub fn verify_signature(
+    message: &[u8],
+    signature: &[u8],
+    pubkey: &[u8],
+) -> Result<CanonicalAddr, ContractError> {
+    let key =
+        VerifyingKey::from_sec1_bytes(pubkey).map_err(|_| ContractError::InvalidSignature {})?;
+    let signature =
+        Signature::try_from(signature).map_err(|_| ContractError::InvalidSignature {})?;
+    key.verify(message, &signature)
+        .map_err(|_| ContractError::InvalidSignature {})?;
+
+    // account address is ripemd160(sha256(compressed pubkey))
+    let hash = Sha256::digest(key.to_encoded_point(true).as_bytes());
+    Ok(CanonicalAddr::from(Ripemd160::digest(&hash)[..].to_vec()))
+}
+
+// split amount by recipient shares, rounding remainder goes to the first recipient
+pub fn split_payout(amount: u128, recipients: &[Recipient]) -> Vec<(CanonicalAddr, u128)> {
+    let mut payouts: Vec<(CanonicalAddr, u128)> = recipients
+        .iter()
+        .map(|r| {
+            (
+                r.addr.clone(),
+                amount * r.share as u128 / BASIS_POINTS as u128,
+            )
+        })
+        .collect();
+    let paid: u128 = payouts.iter().map(|p| p.1).sum();
+    if let Some(first) = payouts.first_mut() {
+        first.1 += amount - paid;
+    }
+    payouts
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::helper::extract_budget_coin;
     use cosmwasm_std::coin;
-    use cosmwasm_std::testing::mock_info;
+    use cosmwasm_std::testing::{mock_env, mock_info};
 
     #[test]
     fn test_extract_funding_coin() {
@@ -28,17 +207,150 @@ mod tests {
         let c = &[coin(4, denom)];
         let info = mock_info("creator", c);
 
-        let res = extract_budget_coin(&info.sent_funds, &denom.to_string());
+        let res = extract_budget_coin(&info.sent_funds, denom);
         match res {
             Ok(cc) => assert_eq!(c, &[cc]),
             Err(err) => println!("{:?}", err),
         }
         let info = mock_info("creator", &[coin(4, denom), coin(4, "test")]);
 
-        match extract_budget_coin(&info.clone().sent_funds, &denom.to_string()) {
+        match extract_budget_coin(&info.clone().sent_funds, denom) {
             Ok(_) => panic!("expected error"),
             Err(ContractError::WrongCoinSent { .. }) => {}
             Err(err) => println!("{:?}", err),
         }
     }
+
+    #[test]
+    fn test_validate_recipients() {
+        let env = mock_env();
+        let share = |addr: &str, share: u64| RecipientShare {
+            addr: HumanAddr::from(addr),
+            share,
+        };
+
+        validate_recipients(&env, &[share("a", 2500), share("b", 7500)], 10).unwrap();
+
+        match validate_recipients(&env, &[share("a", 2500), share("b", 7000)], 10) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidRecipientShares { total: 9500 }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        match validate_recipients(&env, &[], 10) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidRecipientShares { total: 0 }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        match validate_recipients(&env, &[share("a", 10000), share("b", 0)], 10) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ZeroRecipientShare {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        match validate_recipients(&env, &[share("a", 5000), share("a", 5000)], 10) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::DuplicateRecipient { .. }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        let contract = env.contract.address.to_string();
+        match validate_recipients(&env, &[share(&contract, 10000)], 10) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::FundAddressIsContract {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        match validate_recipients(&env, &[share("a", 5000), share("b", 5000)], 1) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::TooManyRecipients { max: 1, got: 2 }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn test_commitment_hash() {
+        let voter = HumanAddr::from("voter");
+        let hash = commitment_hash(&voter, 1, b"salt");
+        assert_eq!(hash.len(), 32);
+        assert_eq!(hash, commitment_hash(&voter, 1, b"salt"));
+        assert_ne!(hash, commitment_hash(&voter, 2, b"salt"));
+        assert_ne!(hash, commitment_hash(&voter, 1, b"pepper"));
+        assert_ne!(hash, commitment_hash(&HumanAddr::from("other"), 1, b"salt"));
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::SigningKey;
+
+        let key = SigningKey::from_bytes(&[1u8; 32]).unwrap();
+        let pubkey = key.verify_key().to_encoded_point(true);
+        let signature: Signature = key.sign(b"message");
+
+        let addr = verify_signature(b"message", signature.as_ref(), pubkey.as_bytes()).unwrap();
+        assert_eq!(addr.len(), 20);
+        // uncompressed key belongs to the same account
+        let uncompressed = key.verify_key().to_encoded_point(false);
+        assert_eq!(
+            addr,
+            verify_signature(b"message", signature.as_ref(), uncompressed.as_bytes()).unwrap()
+        );
+
+        match verify_signature(b"other", signature.as_ref(), pubkey.as_bytes()) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidSignature {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        match verify_signature(b"message", &[0u8; 64], pubkey.as_bytes()) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidSignature {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        match verify_signature(b"message", signature.as_ref(), &[2u8; 33]) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidSignature {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn test_split_payout() {
+        let recipient = |addr: &[u8], share: u64| Recipient {
+            addr: CanonicalAddr(addr.to_vec().into()),
+            share,
+        };
+        let recipients = vec![
+            recipient(b"a", 3333),
+            recipient(b"b", 3333),
+            recipient(b"c", 3334),
+        ];
+
+        let payouts = split_payout(1000, &recipients);
+        let amounts: Vec<u128> = payouts.iter().map(|p| p.1).collect();
+        // remainder goes to first recipient
+        assert_eq!(amounts, vec![334, 333, 333]);
+
+        let payouts = split_payout(0, &recipients);
+        assert!(payouts.iter().all(|p| p.1 == 0));
+    }
+
+    #[test]
+    fn test_extend_period() {
+        let paused_at = PausedAt {
+            height: 100,
+            time: 1000,
+        };
+        let mut env = mock_env();
+        env.block.height = 150;
+        env.block.time = 1300;
+
+        // open periods get the paused duration back
+        let period = extend_period(&Expiration::AtHeight(120), &paused_at, &env.block);
+        assert_eq!(period, Expiration::AtHeight(170));
+        let period = extend_period(&Expiration::AtTime(1100), &paused_at, &env.block);
+        assert_eq!(period, Expiration::AtTime(1400));
+
+        // periods ended before the pause are kept
+        let period = extend_period(&Expiration::AtHeight(100), &paused_at, &env.block);
+        assert_eq!(period, Expiration::AtHeight(100));
+        let period = extend_period(&Expiration::Never {}, &paused_at, &env.block);
+        assert_eq!(period, Expiration::Never {});
+    }
 }
@@ -0,0 +1,251 @@
+use crate::contract::{handle, init, query};
+use crate::msg::{
+    AcceptedDenomsResponse, HandleMsg, InitMsg, OracleQueryMsg, PriceResponse, QueryMsg,
+    VotesResponse,
+};
+use crate::state::Proposal;
+use crate::testing::mock_init_msg;
+use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+use cosmwasm_std::{
+    coin, coins, to_binary, Binary, Coin, Decimal, Deps, DepsMut, Env, HandleResponse, HumanAddr,
+    InitResponse, MessageInfo, StdResult, Uint128,
+};
+use cw_multi_test::{App, Contract, ContractWrapper, SimpleBank};
+use cw_storage_plus::Map;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// mock price oracle quoting prices set by anyone
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct OracleInitMsg {
+    prices: Vec<OraclePrice>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum OracleHandleMsg {
+    SetPrice(OraclePrice),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct OraclePrice {
+    base_denom: String,
+    quote_denom: String,
+    rate: Decimal,
+}
+
+const PRICES: Map<(&[u8], &[u8]), Decimal> = Map::new("prices");
+
+fn set_price(deps: DepsMut, price: OraclePrice) -> StdResult<()> {
+    PRICES.save(
+        deps.storage,
+        (price.base_denom.as_bytes(), price.quote_denom.as_bytes()),
+        &price.rate,
+    )
+}
+
+fn oracle_init(
+    mut deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: OracleInitMsg,
+) -> StdResult<InitResponse> {
+    for price in msg.prices {
+        set_price(deps.branch(), price)?;
+    }
+    Ok(InitResponse::default())
+}
+
+fn oracle_handle(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: OracleHandleMsg,
+) -> StdResult<HandleResponse> {
+    match msg {
+        OracleHandleMsg::SetPrice(price) => set_price(deps, price)?,
+    }
+    Ok(HandleResponse::default())
+}
+
+fn oracle_query(deps: Deps, _env: Env, msg: OracleQueryMsg) -> StdResult<Binary> {
+    match msg {
+        OracleQueryMsg::Price {
+            base_denom,
+            quote_denom,
+        } => {
+            let rate = PRICES
+                .may_load(
+                    deps.storage,
+                    (base_denom.as_bytes(), quote_denom.as_bytes()),
+                )?
+                .unwrap_or_else(Decimal::zero);
+            to_binary(&PriceResponse { rate })
+        }
+    }
+}
+
+fn contract_oracle() -> Box<dyn Contract> {
+    Box::new(ContractWrapper::new(
+        oracle_handle,
+        oracle_init,
+        oracle_query,
+    ))
+}
+
+fn contract_round() -> Box<dyn Contract> {
+    Box::new(ContractWrapper::new(handle, init, query))
+}
+
+fn mock_app() -> App {
+    let env = mock_env();
+    let api = Box::new(MockApi::default());
+    App::new(api, env.block, SimpleBank {}, || {
+        Box::new(MockStorage::new())
+    })
+}
+
+fn round_init_msg(env: &Env, oracle: HumanAddr) -> InitMsg {
+    InitMsg {
+        leftover_addr: HumanAddr::from("admin"),
+        rollover_leftover: Some(true),
+        oracle: Some(oracle),
+        ..mock_init_msg(env)
+    }
+}
+
+fn balance(app: &App, addr: &str, denom: &str) -> Uint128 {
+    app.wrap()
+        .query_balance(addr, denom)
+        .map(|c: Coin| c.amount)
+        .unwrap()
+}
+
+#[test]
+fn oracle_prices_votes() {
+    let env = mock_env();
+    let mut app = mock_app();
+    let admin = HumanAddr::from("admin");
+    app.set_bank_balance(admin.clone(), coins(1000, "ucosm"))
+        .unwrap();
+    app.set_bank_balance(HumanAddr::from("voter1"), coins(1000, "uosmo"))
+        .unwrap();
+    app.set_bank_balance(HumanAddr::from("voter2"), coins(100, "ucosm"))
+        .unwrap();
+    app.set_bank_balance(HumanAddr::from("voter3"), coins(100, "uatom"))
+        .unwrap();
+
+    let oracle_id = app.store_code(contract_oracle());
+    let oracle_price = |rate: Decimal| OraclePrice {
+        base_denom: String::from("uosmo"),
+        quote_denom: String::from("ucosm"),
+        rate,
+    };
+    let oracle = app
+        .instantiate_contract(
+            oracle_id,
+            admin.clone(),
+            &OracleInitMsg {
+                prices: vec![oracle_price(Decimal::percent(50))],
+            },
+            &[],
+            "oracle",
+        )
+        .unwrap();
+
+    let round_id = app.store_code(contract_round());
+    let round = app
+        .instantiate_contract(
+            round_id,
+            admin.clone(),
+            &round_init_msg(&env, oracle.clone()),
+            &coins(1000, "ucosm"),
+            "round",
+        )
+        .unwrap();
+
+    let res: AcceptedDenomsResponse = app
+        .wrap()
+        .query_wasm_smart(&round, &QueryMsg::AcceptedDenoms {})
+        .unwrap();
+    assert_eq!(res.oracle, Some(oracle.clone()));
+
+    let msg = HandleMsg::CreateProposal {
+        title: String::from("test"),
+        description: String::from("test"),
+        metadata: None,
+        fund_address: HumanAddr::from("fund_address"),
+        recipients: None,
+    };
+    app.execute_contract(admin.clone(), round.clone(), &msg, &[])
+        .unwrap();
+
+    // vote is valued with the oracle price
+    let vote = HandleMsg::VoteProposal { proposal_id: 1 };
+    app.execute_contract(
+        HumanAddr::from("voter1"),
+        round.clone(),
+        &vote,
+        &coins(1000, "uosmo"),
+    )
+    .unwrap();
+
+    // denoms the oracle doesn't price are rejected
+    let err = app
+        .execute_contract(
+            HumanAddr::from("voter3"),
+            round.clone(),
+            &vote,
+            &coins(100, "uatom"),
+        )
+        .unwrap_err();
+    assert_eq!(err, "Denom not accepted: uatom");
+
+    // price changes don't affect cast votes
+    app.execute_contract(
+        admin.clone(),
+        oracle,
+        &OracleHandleMsg::SetPrice(oracle_price(Decimal::percent(200))),
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        HumanAddr::from("voter2"),
+        round.clone(),
+        &vote,
+        &coins(100, "ucosm"),
+    )
+    .unwrap();
+
+    let res: VotesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &round,
+            &QueryMsg::VotesByVoter {
+                voter: HumanAddr::from("voter1"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.votes[0].fund, coin(1000, "uosmo"));
+    assert_eq!(res.votes[0].value, Some(Uint128(500)));
+    let proposal: Proposal = app
+        .wrap()
+        .query_wasm_smart(&round, &QueryMsg::ProposalByID { id: 1 })
+        .unwrap();
+    assert_eq!(proposal.collected_funds, Uint128(600));
+
+    // grant is paid in budget denom, contributions in the denom they were sent
+    app.update_block(|b| b.height += 1000);
+    app.execute_contract(
+        admin.clone(),
+        round.clone(),
+        &HandleMsg::TriggerDistribution {},
+        &[],
+    )
+    .unwrap();
+    assert_eq!(balance(&app, "fund_address", "ucosm"), Uint128(1100));
+    assert_eq!(balance(&app, "fund_address", "uosmo"), Uint128(1000));
+    assert_eq!(balance(&app, round.as_str(), "ucosm"), Uint128::zero());
+}
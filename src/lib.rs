@@ -1,9 +1,22 @@
 pub mod contract;
 mod error;
+pub mod factory;
 mod helper;
+#[cfg(test)]
+mod integration_tests;
 mod matching;
+pub mod migrations;
 pub mod msg;
 pub mod state;
+#[cfg(test)]
+mod testing;
 
-#[cfg(target_arch = "wasm32")]
-cosmwasm_std::create_entry_points!(contract);
+#[cfg(all(target_arch = "wasm32", feature = "factory"))]
+use factory::contract as factory_contract;
+
+#[cfg(all(target_arch = "wasm32", not(feature = "factory")))]
+cosmwasm_std::create_entry_points_with_migration!(contract);
+
+// factory is built from the same crate with `--features factory`
+#[cfg(all(target_arch = "wasm32", feature = "factory"))]
+cosmwasm_std::create_entry_points!(factory_contract);
@@ -1,5 +1,5 @@
 use crate::error::ContractError;
-use cosmwasm_std::CanonicalAddr;
+use cosmwasm_std::{CanonicalAddr, Decimal, StdError};
 use integer_sqrt::IntegerSquareRoot;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -10,15 +10,29 @@ pub enum QuadraticFundingAlgorithm {
     CapitalConstrainedLiberalRadicalism { parameter: String },
 }
 
+// voter weights are percentages, WEIGHT_SCALE is neutral
+pub const WEIGHT_SCALE: u64 = 100;
+// highest voter score, keeps weighted matches from overflowing
+pub const MAX_VOTER_SCORE: u64 = 10 * WEIGHT_SCALE;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contribution {
+    pub voter: CanonicalAddr,
+    pub amount: u128,
+    pub weight: u64,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct RawGrant {
+    pub proposal_id: u64,
     pub addr: CanonicalAddr,
-    pub funds: Vec<u128>,
+    pub funds: Vec<Contribution>,
     pub collected_vote_funds: u128,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct CalculatedGrant {
+    pub proposal_id: u64,
     pub addr: CanonicalAddr,
     pub grant: u128,
     pub collected_vote_funds: u128,
@@ -29,14 +43,15 @@ type LeftOver = u128;
 pub fn calculate_clr(
     grants: Vec<RawGrant>,
     budget: Option<u128>,
+    max_matched_contribution: Option<u128>,
 ) -> Result<(Vec<CalculatedGrant>, LeftOver), ContractError> {
     // clr algorithm works with budget constrain
     if let Some(budget) = budget {
         // calculate matches sum
-        let matched = calculate_matched_sum(grants);
+        let matched = calculate_matched_sum(grants, max_matched_contribution)?;
 
         // constraint the grants by budget
-        let constrained = constrain_by_budget(matched, budget);
+        let constrained = constrain_by_budget(matched, budget)?;
 
         let constrained_sum: u128 = constrained.iter().map(|c| c.grant).sum();
         // calculate leftover
@@ -50,39 +65,194 @@ pub fn calculate_clr(
     }
 }
 
-// takes square root of each fund, sums, then squares and returns u128
-fn calculate_matched_sum(grants: Vec<RawGrant>) -> Vec<CalculatedGrant> {
+// how a single proposal's grant is derived by clr
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchBreakdown {
+    // matches are reported with the voter weight scale removed, rounded down
+    pub sum_sqrts: u128,
+    pub raw_match: u128,
+    // grants are scaled by budget / raw_total
+    pub raw_total: u128,
+    pub budget: u128,
+    // budget / raw_total
+    pub scaling_factor: Decimal,
+    pub grant: u128,
+}
+
+pub fn calculate_clr_breakdown(
+    grants: &[RawGrant],
+    proposal_id: u64,
+    budget: Option<u128>,
+    max_matched_contribution: Option<u128>,
+) -> Result<Option<MatchBreakdown>, ContractError> {
+    let budget = budget.ok_or(ContractError::CLRConstrainRequired {})?;
+    let mut raw_total: u128 = 0;
+    for g in grants.iter() {
+        let raw = raw_match(sum_sqrts(&g.funds, max_matched_contribution)?)?;
+        raw_total = raw_total
+            .checked_add(raw)
+            .ok_or(ContractError::Overflow {})?;
+    }
+
+    let g = match grants.iter().find(|g| g.proposal_id == proposal_id) {
+        Some(g) => g,
+        None => return Ok(None),
+    };
+    let sum_sqrts = sum_sqrts(&g.funds, max_matched_contribution)?;
+    let raw_match = raw_match(sum_sqrts)?;
+
+    // weight scale cancels out in the grant, it's only removed from reported matches
+    let scale = (WEIGHT_SCALE * WEIGHT_SCALE) as u128;
+    let scaling_factor = if raw_total == 0 {
+        Decimal::one()
+    } else {
+        budget
+            .checked_mul(scale)
+            .and_then(|budget| decimal_ratio(budget, raw_total))
+            .ok_or_else(|| StdError::generic_err("Scaling factor overflow"))?
+    };
+
+    Ok(Some(MatchBreakdown {
+        sum_sqrts: sum_sqrts / WEIGHT_SCALE as u128,
+        raw_match: raw_match / scale,
+        raw_total: raw_total / scale,
+        budget,
+        scaling_factor,
+        grant: constrained_grant(raw_match, raw_total, budget)?,
+    }))
+}
+
+// nominator / denominator as a decimal, unlike Decimal::from_ratio it doesn't overflow
+// once nominator * 10^18 exceeds u128
+fn decimal_ratio(nominator: u128, denominator: u128) -> Option<Decimal> {
+    const FRACTIONAL: u128 = 1_000_000_000_000_000_000;
+    let whole = nominator / denominator;
+    if whole > u128::MAX / FRACTIONAL {
+        return None;
+    }
+
+    // long division of the remainder, one decimal digit at a time
+    let mut remainder = nominator % denominator;
+    let mut denominator = denominator;
+    while denominator > u128::MAX / 10 {
+        remainder /= 10;
+        denominator /= 10;
+    }
+    let mut fraction = 0u128;
+    for _ in 0..18 {
+        remainder *= 10;
+        fraction = fraction * 10 + remainder / denominator;
+        remainder %= denominator;
+    }
+
+    Some(Decimal::from_ratio(whole, 1u128) + Decimal::from_ratio(fraction, FRACTIONAL))
+}
+
+// takes square root of each fund capped by max contribution multiplied by voter weight,
+// sums, then squares and returns u128
+fn calculate_matched_sum(
+    grants: Vec<RawGrant>,
+    max_contribution: Option<u128>,
+) -> Result<Vec<CalculatedGrant>, ContractError> {
     grants
         .into_iter()
         .map(|g| {
-            let sum_sqrts: u128 = g.funds.into_iter().map(|v| v.integer_sqrt()).sum();
-            CalculatedGrant {
+            Ok(CalculatedGrant {
+                proposal_id: g.proposal_id,
+                grant: raw_match(sum_sqrts(&g.funds, max_contribution)?)?,
                 addr: g.addr,
-                grant: sum_sqrts * sum_sqrts,
                 collected_vote_funds: g.collected_vote_funds,
-            }
+            })
         })
         .collect()
 }
 
-// takes square root of each fund, sums, then squares and returns u128
-fn constrain_by_budget(grants: Vec<CalculatedGrant>, budget: u128) -> Vec<CalculatedGrant> {
-    let raw_total: u128 = grants.iter().map(|g| g.grant).sum();
+// sum of square roots of funds capped by max contribution, multiplied by voter weight
+fn sum_sqrts(
+    funds: &[Contribution],
+    max_contribution: Option<u128>,
+) -> Result<u128, ContractError> {
+    let mut sum: u128 = 0;
+    for c in funds {
+        let amount = max_contribution.map_or(c.amount, |max| c.amount.min(max));
+        sum = amount
+            .integer_sqrt()
+            .checked_mul(c.weight as u128)
+            .and_then(|weighted| sum.checked_add(weighted))
+            .ok_or(ContractError::Overflow {})?;
+    }
+    Ok(sum)
+}
+
+// squares sum of square roots, in WEIGHT_SCALE^2 units so small matches aren't truncated
+fn raw_match(sum_sqrts: u128) -> Result<u128, ContractError> {
+    sum_sqrts
+        .checked_mul(sum_sqrts)
+        .ok_or(ContractError::Overflow {})
+}
+
+// scales raw match to its share of the budget
+fn constrained_grant(
+    raw_match: u128,
+    raw_total: u128,
+    budget: u128,
+) -> Result<u128, ContractError> {
+    // nothing to match, whole budget is leftover
+    if raw_total == 0 {
+        return Ok(raw_match);
+    }
+    raw_match
+        .checked_mul(budget)
+        .map(|scaled| scaled / raw_total)
+        .ok_or(ContractError::Overflow {})
+}
+
+// scales each grant to its share of the budget
+fn constrain_by_budget(
+    grants: Vec<CalculatedGrant>,
+    budget: u128,
+) -> Result<Vec<CalculatedGrant>, ContractError> {
+    let mut raw_total: u128 = 0;
+    for g in grants.iter() {
+        raw_total = raw_total
+            .checked_add(g.grant)
+            .ok_or(ContractError::Overflow {})?;
+    }
     grants
         .into_iter()
-        .map(|g| CalculatedGrant {
-            addr: g.addr,
-            grant: (g.grant * budget) / raw_total,
-            collected_vote_funds: g.collected_vote_funds,
+        .map(|g| {
+            Ok(CalculatedGrant {
+                proposal_id: g.proposal_id,
+                addr: g.addr,
+                grant: constrained_grant(g.grant, raw_total, budget)?,
+                collected_vote_funds: g.collected_vote_funds,
+            })
         })
         .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::matching::{calculate_clr, CalculatedGrant, RawGrant};
+    use crate::error::ContractError;
+    use crate::matching::{
+        calculate_clr, calculate_clr_breakdown, CalculatedGrant, Contribution, MatchBreakdown,
+        RawGrant, MAX_VOTER_SCORE, WEIGHT_SCALE,
+    };
     use crate::state::Proposal;
-    use cosmwasm_std::CanonicalAddr;
+    use cosmwasm_std::{CanonicalAddr, Decimal};
+
+    // contributions of distinct voters with neutral weight
+    fn contributions(amounts: &[u128]) -> Vec<Contribution> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(i, amount)| Contribution {
+                voter: CanonicalAddr(format!("voter{}", i).into_bytes().into()),
+                amount: *amount,
+                weight: WEIGHT_SCALE,
+            })
+            .collect()
+    }
 
     #[test]
     fn test_clr_1() {
@@ -109,49 +279,57 @@ mod tests {
 
         let grants = vec![
             RawGrant {
+                proposal_id: 1,
                 addr: proposal1.fund_address.clone(),
-                funds: votes1.clone(),
+                funds: contributions(&votes1),
                 collected_vote_funds: votes1.iter().sum(),
             },
             RawGrant {
+                proposal_id: 2,
                 addr: proposal2.fund_address.clone(),
-                funds: votes2.clone(),
+                funds: contributions(&votes2),
                 collected_vote_funds: votes2.iter().sum(),
             },
             RawGrant {
+                proposal_id: 3,
                 addr: proposal3.fund_address.clone(),
-                funds: votes3.clone(),
+                funds: contributions(&votes3),
                 collected_vote_funds: votes3.iter().sum(),
             },
             RawGrant {
+                proposal_id: 4,
                 addr: proposal4.fund_address.clone(),
-                funds: votes4.clone(),
+                funds: contributions(&votes4),
                 collected_vote_funds: votes4.iter().sum(),
             },
         ];
         let expected = vec![
             CalculatedGrant {
+                proposal_id: 1,
                 addr: proposal1.fund_address,
                 grant: 84737u128,
                 collected_vote_funds: 7200u128,
             },
             CalculatedGrant {
+                proposal_id: 2,
                 addr: proposal2.fund_address,
                 grant: 147966u128,
                 collected_vote_funds: 12345u128,
             },
             CalculatedGrant {
+                proposal_id: 3,
                 addr: proposal3.fund_address,
                 grant: 52312u128,
                 collected_vote_funds: 4456u128,
             },
             CalculatedGrant {
+                proposal_id: 4,
                 addr: proposal4.fund_address,
                 grant: 714983u128,
                 collected_vote_funds: 60000u128,
             },
         ];
-        let res = calculate_clr(grants, Some(1000000u128));
+        let res = calculate_clr(grants, Some(1000000u128), None);
         match res {
             Ok(o) => {
                 assert_eq!(o.0, expected);
@@ -192,49 +370,57 @@ mod tests {
 
         let grants = vec![
             RawGrant {
+                proposal_id: 1,
                 addr: proposal1.fund_address.clone(),
-                funds: votes1.clone(),
+                funds: contributions(&votes1),
                 collected_vote_funds: votes1.iter().sum(),
             },
             RawGrant {
+                proposal_id: 2,
                 addr: proposal2.fund_address.clone(),
-                funds: votes2.clone(),
+                funds: contributions(&votes2),
                 collected_vote_funds: votes2.iter().sum(),
             },
             RawGrant {
+                proposal_id: 3,
                 addr: proposal3.fund_address.clone(),
-                funds: votes3.clone(),
+                funds: contributions(&votes3),
                 collected_vote_funds: votes3.iter().sum(),
             },
             RawGrant {
+                proposal_id: 4,
                 addr: proposal4.fund_address.clone(),
-                funds: votes4.clone(),
+                funds: contributions(&votes4),
                 collected_vote_funds: votes4.iter().sum(),
             },
         ];
         let expected = vec![
             CalculatedGrant {
+                proposal_id: 1,
                 addr: proposal1.fund_address,
                 grant: 60212u128,
                 collected_vote_funds: votes1.iter().sum(),
             },
             CalculatedGrant {
+                proposal_id: 2,
                 addr: proposal2.fund_address,
                 grant: 164602u128,
                 collected_vote_funds: votes2.iter().sum(),
             },
             CalculatedGrant {
+                proposal_id: 3,
                 addr: proposal3.fund_address,
                 grant: 228537u128,
                 collected_vote_funds: votes3.iter().sum(),
             },
             CalculatedGrant {
+                proposal_id: 4,
                 addr: proposal4.fund_address,
                 grant: 96648u128,
                 collected_vote_funds: votes4.iter().sum(),
             },
         ];
-        let res = calculate_clr(grants, Some(550000u128));
+        let res = calculate_clr(grants, Some(550000u128), None);
         match res {
             Ok(o) => {
                 assert_eq!(o.0, expected);
@@ -243,4 +429,229 @@ mod tests {
             e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
     }
+
+    #[test]
+    fn test_clr_weighted() {
+        let mut votes1 = contributions(&[10000u128, 10000u128]);
+        let votes2 = contributions(&[10000u128, 10000u128]);
+        // trust bonus of the first voter doubles the square root of the contribution
+        votes1[0].weight = 2 * WEIGHT_SCALE;
+
+        let grants = vec![
+            RawGrant {
+                proposal_id: 1,
+                addr: CanonicalAddr(b"proposal1".to_vec().into()),
+                funds: votes1,
+                collected_vote_funds: 20000u128,
+            },
+            RawGrant {
+                proposal_id: 2,
+                addr: CanonicalAddr(b"proposal2".to_vec().into()),
+                funds: votes2,
+                collected_vote_funds: 20000u128,
+            },
+        ];
+        // (2 * 100 + 100)^2 = 90000 vs (100 + 100)^2 = 40000
+        let expected = vec![
+            CalculatedGrant {
+                proposal_id: 1,
+                addr: CanonicalAddr(b"proposal1".to_vec().into()),
+                grant: 9000u128,
+                collected_vote_funds: 20000u128,
+            },
+            CalculatedGrant {
+                proposal_id: 2,
+                addr: CanonicalAddr(b"proposal2".to_vec().into()),
+                grant: 4000u128,
+                collected_vote_funds: 20000u128,
+            },
+        ];
+        let res = calculate_clr(grants, Some(13000u128), None);
+        match res {
+            Ok(o) => {
+                assert_eq!(o.0, expected);
+                assert_eq!(o.1, 0)
+            }
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn test_clr_cw4_weights() {
+        // cw4 member weights are usually 1, far below neutral weight
+        let weighted = |amounts: &[u128]| {
+            let mut funds = contributions(amounts);
+            for c in funds.iter_mut() {
+                c.weight = 1;
+            }
+            funds
+        };
+        let grants = vec![
+            RawGrant {
+                proposal_id: 1,
+                addr: CanonicalAddr(b"proposal1".to_vec().into()),
+                funds: weighted(&[100u128, 100u128, 100u128]),
+                collected_vote_funds: 300u128,
+            },
+            RawGrant {
+                proposal_id: 2,
+                addr: CanonicalAddr(b"proposal2".to_vec().into()),
+                funds: weighted(&[100u128]),
+                collected_vote_funds: 100u128,
+            },
+        ];
+        // (3 * 10)^2 = 900 vs 10^2 = 100, small matches aren't truncated to zero
+        let expected = vec![
+            CalculatedGrant {
+                proposal_id: 1,
+                addr: CanonicalAddr(b"proposal1".to_vec().into()),
+                grant: 900u128,
+                collected_vote_funds: 300u128,
+            },
+            CalculatedGrant {
+                proposal_id: 2,
+                addr: CanonicalAddr(b"proposal2".to_vec().into()),
+                grant: 100u128,
+                collected_vote_funds: 100u128,
+            },
+        ];
+        let res = calculate_clr(grants, Some(1000u128), None);
+        match res {
+            Ok(o) => {
+                assert_eq!(o.0, expected);
+                assert_eq!(o.1, 0)
+            }
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn test_clr_max_matched_contribution() {
+        let grants = vec![
+            RawGrant {
+                proposal_id: 1,
+                addr: CanonicalAddr(b"proposal1".to_vec().into()),
+                funds: contributions(&[1000000u128]),
+                collected_vote_funds: 1000000u128,
+            },
+            RawGrant {
+                proposal_id: 2,
+                addr: CanonicalAddr(b"proposal2".to_vec().into()),
+                funds: contributions(&[10000u128]),
+                collected_vote_funds: 10000u128,
+            },
+        ];
+        // whale contribution only counts as 10000 for matching
+        let expected = vec![
+            CalculatedGrant {
+                proposal_id: 1,
+                addr: CanonicalAddr(b"proposal1".to_vec().into()),
+                grant: 500u128,
+                collected_vote_funds: 1000000u128,
+            },
+            CalculatedGrant {
+                proposal_id: 2,
+                addr: CanonicalAddr(b"proposal2".to_vec().into()),
+                grant: 500u128,
+                collected_vote_funds: 10000u128,
+            },
+        ];
+        let res = calculate_clr(grants, Some(1000u128), Some(10000u128));
+        match res {
+            Ok(o) => {
+                assert_eq!(o.0, expected);
+                assert_eq!(o.1, 0)
+            }
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
+
+    #[test]
+    fn test_clr_breakdown() {
+        let grants = vec![
+            RawGrant {
+                proposal_id: 1,
+                addr: CanonicalAddr(b"proposal1".to_vec().into()),
+                funds: contributions(&[100]),
+                collected_vote_funds: 100,
+            },
+            RawGrant {
+                proposal_id: 2,
+                addr: CanonicalAddr(b"proposal2".to_vec().into()),
+                funds: contributions(&[400, 100]),
+                collected_vote_funds: 500,
+            },
+        ];
+
+        let breakdown = calculate_clr_breakdown(&grants, 2, Some(500), None).unwrap();
+        let expected = MatchBreakdown {
+            // sqrt(400) + sqrt(100)
+            sum_sqrts: 30,
+            raw_match: 900,
+            raw_total: 1000,
+            budget: 500,
+            scaling_factor: Decimal::percent(50),
+            grant: 450,
+        };
+        assert_eq!(breakdown, Some(expected));
+
+        // scaling factor of a budget of 1000 tokens with 18 decimals doesn't overflow
+        let budget = 1000 * 10u128.pow(18);
+        let breakdown = calculate_clr_breakdown(&grants, 2, Some(budget), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            breakdown.scaling_factor,
+            Decimal::from_ratio(10u128.pow(18), 1u128)
+        );
+        assert_eq!(breakdown.grant, budget / 1000 * 900);
+
+        // fractional scaling factor
+        let breakdown = calculate_clr_breakdown(&grants, 2, Some(1000 / 3), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            breakdown.scaling_factor,
+            "0.333".parse::<Decimal>().unwrap()
+        );
+
+        // breakdown matches distribution
+        let (distributed, _) = calculate_clr(grants.clone(), Some(500), None).unwrap();
+        for g in distributed {
+            let breakdown = calculate_clr_breakdown(&grants, g.proposal_id, Some(500), None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(breakdown.grant, g.grant);
+        }
+
+        // unknown proposal
+        let breakdown = calculate_clr_breakdown(&grants, 3, Some(500), None).unwrap();
+        assert_eq!(breakdown, None);
+    }
+
+    #[test]
+    fn test_clr_overflow() {
+        let grants = vec![RawGrant {
+            proposal_id: 1,
+            addr: CanonicalAddr(b"proposal1".to_vec().into()),
+            funds: vec![Contribution {
+                voter: CanonicalAddr(b"voter".to_vec().into()),
+                amount: u128::MAX,
+                weight: MAX_VOTER_SCORE,
+            }],
+            collected_vote_funds: u128::MAX,
+        }];
+
+        // overflowing matches fail instead of wrapping
+        match calculate_clr(grants.clone(), Some(1000u128), None) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Overflow {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+        match calculate_clr_breakdown(&grants, 1, Some(1000u128), None) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::Overflow {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+    }
 }
@@ -0,0 +1,229 @@
+use crate::matching::QuadraticFundingAlgorithm;
+use crate::state::{
+    Config, Proposal, ProposalStatus, Vote, CONFIG, CREATE_PROPOSAL_WHITELIST,
+    DEFAULT_DESCRIPTION_LENGTH, DEFAULT_MAX_RECIPIENTS, DEFAULT_METADATA_LENGTH,
+    DEFAULT_TITLE_LENGTH, PROPOSALS, VOTER_VOTES, VOTES, VOTE_PROPOSAL_WHITELIST,
+};
+use cosmwasm_std::{Binary, CanonicalAddr, Coin, Empty, Order, StdResult, Storage, Uint128};
+use cw0::Expiration;
+use cw_storage_plus::{Item, Map, U64Key};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// config layout before cw2 version tracking was added
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub admin: CanonicalAddr,
+    pub leftover_addr: CanonicalAddr,
+    pub create_proposal_whitelist: Option<Vec<CanonicalAddr>>,
+    pub vote_proposal_whitelist: Option<Vec<CanonicalAddr>>,
+    pub voting_period: Expiration,
+    pub proposal_period: Expiration,
+    pub budget: Coin,
+    pub algorithm: QuadraticFundingAlgorithm,
+}
+
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyProposal {
+    pub id: u64,
+    pub title: String,
+    pub description: String,
+    pub metadata: Option<Binary>,
+    pub fund_address: CanonicalAddr,
+    pub collected_funds: Uint128,
+}
+
+pub const LEGACY_PROPOSALS: Map<U64Key, LegacyProposal> = Map::new("proposal");
+
+// migrates state written before cw2 version tracking to the current layout
+pub fn migrate_legacy_state(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy = LEGACY_CONFIG.load(storage)?;
+    let create_proposal_whitelist_enabled = legacy.create_proposal_whitelist.is_some();
+    for addr in legacy.create_proposal_whitelist.unwrap_or_default() {
+        CREATE_PROPOSAL_WHITELIST.save(storage, addr.as_slice(), &Empty {})?;
+    }
+    let vote_proposal_whitelist_enabled = legacy.vote_proposal_whitelist.is_some();
+    for addr in legacy.vote_proposal_whitelist.unwrap_or_default() {
+        VOTE_PROPOSAL_WHITELIST.save(storage, addr.as_slice(), &Empty {})?;
+    }
+    let config = Config {
+        admin: Some(legacy.admin),
+        pending_admin: None,
+        leftover_addr: legacy.leftover_addr,
+        create_proposal_whitelist_enabled,
+        vote_proposal_whitelist_enabled,
+        proposer_group: None,
+        voter_group: None,
+        voter_weight: None,
+        min_contribution: Uint128::zero(),
+        min_unique_contributors: 0,
+        max_matched_contribution: None,
+        voting_period: legacy.voting_period,
+        proposal_period: legacy.proposal_period,
+        budget: legacy.budget,
+        algorithm: legacy.algorithm,
+        title_length: DEFAULT_TITLE_LENGTH,
+        description_length: DEFAULT_DESCRIPTION_LENGTH,
+        metadata_length: DEFAULT_METADATA_LENGTH,
+        proposal_deposit: None,
+        paused: false,
+        paused_at: None,
+        rollover_leftover: false,
+        round: 1,
+        distributed: false,
+        oracle: None,
+        commit_reveal: None,
+        max_recipients: DEFAULT_MAX_RECIPIENTS,
+    };
+    CONFIG.save(storage, &config)?;
+
+    let proposals: StdResult<Vec<(Vec<u8>, LegacyProposal)>> = LEGACY_PROPOSALS
+        .range(storage, None, None, Order::Ascending)
+        .collect();
+    for (_, p) in proposals? {
+        // proposer is unknown for legacy proposals, so they can't be edited
+        let proposal = Proposal {
+            id: p.id,
+            title: p.title,
+            description: p.description,
+            metadata: p.metadata,
+            fund_address: p.fund_address,
+            collected_funds: p.collected_funds,
+            matched_funds: p.collected_funds,
+            proposer: CanonicalAddr::default(),
+            status: ProposalStatus::Active,
+            deposit: None,
+            recipients: vec![],
+        };
+        PROPOSALS.save(storage, p.id.into(), &proposal)?;
+    }
+
+    migrate_vote_keys(storage)
+}
+
+// rewrites votes keyed by human address bytes to canonical address keys
+// and builds the voter index
+pub fn migrate_vote_keys(storage: &mut dyn Storage) -> StdResult<()> {
+    let ids: StdResult<Vec<u64>> = PROPOSALS
+        .range(storage, None, None, Order::Ascending)
+        .map(|p| p.map(|(_, p)| p.id))
+        .collect();
+
+    for id in ids? {
+        let votes: StdResult<Vec<(Vec<u8>, Vote)>> = VOTES
+            .prefix(id.into())
+            .range(storage, None, None, Order::Ascending)
+            .collect();
+
+        for (key, vote) in votes? {
+            if key != vote.voter.as_slice() {
+                VOTES.remove(storage, (id.into(), &key));
+                VOTES.save(storage, (id.into(), vote.voter.as_slice()), &vote)?;
+            }
+            VOTER_VOTES.save(storage, (vote.voter.as_slice(), id.into()), &Empty {})?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Proposal;
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{coin, Api, HumanAddr};
+
+    #[test]
+    fn test_migrate_vote_keys() {
+        let mut deps = mock_dependencies(&[]);
+
+        let proposal = Proposal {
+            id: 1,
+            ..Default::default()
+        };
+        PROPOSALS
+            .save(&mut deps.storage, 1_u64.into(), &proposal)
+            .unwrap();
+
+        // votes keyed by human address
+        let voter = HumanAddr::from("voter");
+        let vote = Vote {
+            proposal_id: 1,
+            voter: deps.api.canonical_address(&voter).unwrap(),
+            fund: coin(100, "ucosm"),
+            value: None,
+            curator: None,
+            weight: None,
+        };
+        VOTES
+            .save(&mut deps.storage, (1_u64.into(), voter.as_bytes()), &vote)
+            .unwrap();
+
+        migrate_vote_keys(&mut deps.storage).unwrap();
+
+        let old = VOTES
+            .may_load(&deps.storage, (1_u64.into(), voter.as_bytes()))
+            .unwrap();
+        assert_eq!(old, None);
+        let new = VOTES
+            .load(&deps.storage, (1_u64.into(), vote.voter.as_slice()))
+            .unwrap();
+        assert_eq!(new, vote);
+        let index = VOTER_VOTES
+            .may_load(&deps.storage, (vote.voter.as_slice(), 1_u64.into()))
+            .unwrap();
+        assert_eq!(index, Some(Empty {}));
+    }
+
+    #[test]
+    fn test_migrate_legacy_state() {
+        let mut deps = mock_dependencies(&[]);
+
+        let admin = deps.api.canonical_address(&"admin".into()).unwrap();
+        let member = deps.api.canonical_address(&"member".into()).unwrap();
+        let legacy = LegacyConfig {
+            admin: admin.clone(),
+            leftover_addr: admin.clone(),
+            create_proposal_whitelist: Some(vec![member.clone()]),
+            vote_proposal_whitelist: None,
+            voting_period: Expiration::AtHeight(100),
+            proposal_period: Expiration::AtHeight(50),
+            budget: coin(1000, "ucosm"),
+            algorithm: QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
+                parameter: "".to_string(),
+            },
+        };
+        LEGACY_CONFIG.save(&mut deps.storage, &legacy).unwrap();
+        let proposal = LegacyProposal {
+            id: 1,
+            title: "title".to_string(),
+            collected_funds: Uint128(100),
+            ..Default::default()
+        };
+        LEGACY_PROPOSALS
+            .save(&mut deps.storage, 1_u64.into(), &proposal)
+            .unwrap();
+
+        migrate_legacy_state(&mut deps.storage).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.admin, Some(admin));
+        assert!(config.create_proposal_whitelist_enabled);
+        assert!(!config.vote_proposal_whitelist_enabled);
+        assert_eq!(config.title_length, DEFAULT_TITLE_LENGTH);
+        assert_eq!(config.max_recipients, DEFAULT_MAX_RECIPIENTS);
+        assert!(CREATE_PROPOSAL_WHITELIST
+            .may_load(&deps.storage, member.as_slice())
+            .unwrap()
+            .is_some());
+
+        let migrated = PROPOSALS.load(&deps.storage, 1_u64.into()).unwrap();
+        assert_eq!(migrated.title, "title");
+        assert_eq!(migrated.collected_funds, Uint128(100));
+        assert_eq!(migrated.matched_funds, Uint128(100));
+        assert_eq!(migrated.status, ProposalStatus::Active);
+    }
+}
@@ -1,7 +1,11 @@
 use crate::error::ContractError;
+use crate::helper::validate_reveal_period;
 use crate::matching::QuadraticFundingAlgorithm;
-use crate::state::Proposal;
-use cosmwasm_std::{Binary, Env, HumanAddr};
+use crate::state::{
+    CommitReveal, Delegation, LengthLimit, Proposal, ProposalStatus, Rollover, Vote,
+    VoterWeightSource, Whitelist,
+};
+use cosmwasm_std::{Binary, Coin, Decimal, Env, HumanAddr, Uint128};
 use cw0::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -13,10 +17,36 @@ pub struct InitMsg {
     pub leftover_addr: HumanAddr,
     pub create_proposal_whitelist: Option<Vec<HumanAddr>>,
     pub vote_proposal_whitelist: Option<Vec<HumanAddr>>,
+    // cw4 group contracts used as proposer and voter registries instead of whitelists
+    pub proposer_group: Option<HumanAddr>,
+    pub voter_group: Option<HumanAddr>,
+    // multiplies square rooted contributions in matching, neutral if not set
+    pub voter_weight: Option<VoterWeightSource>,
+    pub min_contribution: Option<Uint128>,
+    pub min_unique_contributors: Option<u64>,
+    pub max_matched_contribution: Option<Uint128>,
     pub voting_period: Expiration,
     pub proposal_period: Expiration,
     pub budget_denom: String,
     pub algorithm: QuadraticFundingAlgorithm,
+    // proposal field byte length bounds, defaults are used if not set
+    pub title_length: Option<LengthLimit>,
+    pub description_length: Option<LengthLimit>,
+    pub metadata_length: Option<LengthLimit>,
+    // most payout recipients a proposal can split its grant between, 10 if not set
+    pub max_recipients: Option<u64>,
+    // deposit required to create a proposal
+    pub proposal_deposit: Option<Coin>,
+    // keeps distribution leftover for the next round instead of sending it to leftover_addr
+    pub rollover_leftover: Option<bool>,
+    // factory contract the round registers itself with on init
+    pub factory: Option<HumanAddr>,
+    // denoms accepted for votes besides budget_denom, with their rate into budget units
+    pub accepted_denoms: Option<Vec<DenomRate>>,
+    // price oracle used instead of accepted_denoms rates, see OracleQueryMsg
+    pub oracle: Option<HumanAddr>,
+    // votes are committed during voting and revealed after it, public if not set
+    pub commit_reveal: Option<CommitReveal>,
 }
 
 impl InitMsg {
@@ -29,6 +59,45 @@ impl InitMsg {
         if self.voting_period.is_expired(&env.block) {
             return Err(ContractError::VotingPeriodExpired {});
         }
+        // check whitelist and group are not used together
+        if (self.create_proposal_whitelist.is_some() && self.proposer_group.is_some())
+            || (self.vote_proposal_whitelist.is_some() && self.voter_group.is_some())
+        {
+            return Err(ContractError::WhitelistAndGroupConflict {});
+        }
+        // check reveal period is not expired
+        if let Some(commit_reveal) = &self.commit_reveal {
+            if commit_reveal.reveal_period.is_expired(&env.block) {
+                return Err(ContractError::RevealPeriodExpired {});
+            }
+            validate_reveal_period(&commit_reveal.reveal_period, &self.voting_period)?;
+        }
+        // check cw4 weights have a group to query
+        if self.voter_weight == Some(VoterWeightSource::Cw4Group {}) && self.voter_group.is_none() {
+            return Err(ContractError::VoterGroupRequired {});
+        }
+        // check proposal deposit is not zero
+        if let Some(deposit) = &self.proposal_deposit {
+            if deposit.amount.is_zero() {
+                return Err(ContractError::ZeroProposalDeposit {});
+            }
+        }
+        // check length limits are well formed
+        for limit in [
+            &self.title_length,
+            &self.description_length,
+            &self.metadata_length,
+        ]
+        .iter()
+        .filter_map(|l| l.as_ref())
+        {
+            if limit.min > limit.max {
+                return Err(ContractError::InvalidLengthLimit {
+                    min: limit.min,
+                    max: limit.max,
+                });
+            }
+        }
 
         Ok(())
     }
@@ -42,18 +111,214 @@ pub enum HandleMsg {
         description: String,
         metadata: Option<Binary>,
         fund_address: HumanAddr,
+        // splits payout among recipients, shares in basis points summing to 10000
+        recipients: Option<Vec<RecipientShare>>,
+    },
+    UpdateProposal {
+        id: u64,
+        title: Option<String>,
+        description: Option<String>,
+        metadata: Option<Binary>,
+        fund_address: Option<HumanAddr>,
+        // empty list removes the split
+        recipients: Option<Vec<RecipientShare>>,
+    },
+    WithdrawProposal {
+        id: u64,
     },
     VoteProposal {
         proposal_id: u64,
     },
+    // commit reveal rounds only, escrows sent funds during voting,
+    // commitment is sha256(voter || proposal_id as 8 big endian bytes || salt)
+    CommitVote {
+        commitment: Binary,
+    },
+    // counts a committed vote during the reveal period,
+    // votes on rejected or withdrawn proposals are refunded instead
+    RevealVote {
+        proposal_id: u64,
+        salt: Binary,
+    },
+    // escrows sent funds for curator to allocate as votes of the sender
+    DelegateFunds {
+        curator: HumanAddr,
+    },
+    // curator only, votes are attributed to delegator
+    CuratorAllocate {
+        delegator: HumanAddr,
+        allocations: Vec<Allocation>,
+    },
+    // refunds unallocated funds while voting is open
+    RevokeDelegation {
+        curator: HumanAddr,
+    },
+    // deposits sent funds for voter's relayed votes, unused funds are refunded to voter on distribution
+    DepositFunds {
+        voter: HumanAddr,
+    },
+    // anyone can relay a vote signed by voter, paid from voter's deposit,
+    // payload is a json encoded RelayedVotePayload,
+    // signature is secp256k1 over sha256(payload) as 64 bytes r || s
+    RelayedVote {
+        voter: HumanAddr,
+        pubkey: Binary,
+        payload: Binary,
+        signature: Binary,
+    },
+    SetProposalStatus {
+        id: u64,
+        status: ProposalStatus,
+    },
     TriggerDistribution {},
+    UpdateConfig {
+        leftover_addr: Option<HumanAddr>,
+        rollover_leftover: Option<bool>,
+        voting_period: Option<Expiration>,
+        proposal_period: Option<Expiration>,
+        algorithm: Option<QuadraticFundingAlgorithm>,
+        oracle: Option<HumanAddr>,
+    },
+    ProposeNewAdmin {
+        addr: HumanAddr,
+    },
+    AcceptAdmin {},
+    RenounceAdmin {},
+    AddToWhitelist {
+        whitelist: Whitelist,
+        addrs: Vec<HumanAddr>,
+    },
+    RemoveFromWhitelist {
+        whitelist: Whitelist,
+        addrs: Vec<HumanAddr>,
+    },
+    SetVoterScores {
+        scores: Vec<VoterScore>,
+    },
+    // admin only, sets rates of denoms accepted for votes,
+    // votes keep the value they were cast with
+    SetDenomRates {
+        rates: Vec<DenomRate>,
+    },
+    RemoveDenoms {
+        denoms: Vec<String>,
+    },
+    // opens a new round once the current one is distributed,
+    // budget is the rolled over leftover plus sent funds
+    StartNextRound {
+        voting_period: Expiration,
+        proposal_period: Expiration,
+        // required in commit reveal rounds
+        reveal_period: Option<Expiration>,
+    },
+    // freezes every action except unpausing, periods still open when paused
+    // are extended by the paused duration on unpause
+    Pause {},
+    Unpause {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoterScore {
+    pub addr: HumanAddr,
+    // percentage, 100 is neutral, at most 1000
+    pub score: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Allocation {
+    pub proposal_id: u64,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RelayedVotePayload {
+    // round contract the vote is cast on
+    pub contract: HumanAddr,
+    pub proposal_id: u64,
+    pub amount: Uint128,
+    // voter's next relay nonce, see QueryMsg::RelayAccount
+    pub nonce: u64,
+    pub expiry: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecipientShare {
+    pub addr: HumanAddr,
+    pub share: u64,
+}
+
+// rate is the budget units one unit of denom is worth
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomRate {
+    pub denom: String,
+    pub rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+// query interface expected from the price oracle
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleQueryMsg {
+    Price {
+        base_denom: String,
+        quote_denom: String,
+    },
+}
+
+// quote_denom units one unit of base_denom is worth, zero if not priced
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceResponse {
+    pub rate: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    ProposalByID { id: u64 },
-    AllProposals {},
+    ProposalByID {
+        id: u64,
+    },
+    // hidden proposals are only listed if status is Hidden
+    AllProposals {
+        status: Option<ProposalStatus>,
+    },
+    Config {},
+    WhitelistMembers {
+        whitelist: Whitelist,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    VotesByVoter {
+        voter: HumanAddr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // delegations left to curator to allocate
+    DelegationsByCurator {
+        curator: HumanAddr,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    // deposit and next nonce of voter's relayed votes
+    RelayAccount {
+        voter: HumanAddr,
+    },
+    // denoms accepted for votes, budget denom first
+    AcceptedDenoms {},
+    // how the proposal's grant is derived from current votes
+    ProposalMatchBreakdown {
+        id: u64,
+    },
+    // distribution result of the given round, latest distributed round if not set
+    DistributionResult {
+        round: Option<u64>,
+    },
+    // leftovers rolled over into following rounds, by round
+    Rollovers {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -61,9 +326,60 @@ pub struct AllProposalsResponse {
     pub proposals: Vec<Proposal>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistMembersResponse {
+    pub members: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VotesResponse {
+    pub votes: Vec<Vote>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegationsResponse {
+    pub delegations: Vec<Delegation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RelayAccountResponse {
+    pub deposit: Option<Coin>,
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AcceptedDenomsResponse {
+    pub denoms: Vec<DenomRate>,
+    // any denom priced by the oracle is accepted if set, listed rates are not used
+    pub oracle: Option<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalMatchBreakdownResponse {
+    pub proposal_id: u64,
+    // voters on the proposal, none are matched under min_unique_contributors
+    pub contributors: u64,
+    // sum of square roots multiplied by voter weights, weight percentages applied
+    // as fractions and rounded down like raw_match and raw_total
+    pub sum_of_square_roots: Uint128,
+    // squared sum of square roots before budget scaling
+    pub raw_match: Uint128,
+    // raw matches of all proposals, grant is raw_match * budget / raw_total
+    pub raw_total: Uint128,
+    pub budget: Uint128,
+    pub scaling_factor: Decimal,
+    pub grant: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RolloversResponse {
+    pub rollovers: Vec<Rollover>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::mock_init_msg;
     use cosmwasm_std::testing::mock_env;
 
     #[test]
@@ -71,25 +387,14 @@ mod tests {
         let mut env = mock_env();
 
         env.block.height = 30;
-        let msg = InitMsg {
-            admin: Default::default(),
-            leftover_addr: Default::default(),
-            create_proposal_whitelist: None,
-            vote_proposal_whitelist: None,
-            voting_period: Default::default(),
-            proposal_period: Default::default(),
-            budget_denom: "".to_string(),
-            algorithm: QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
-                parameter: "".to_string(),
-            },
-        };
+        let msg = mock_init_msg(&env);
 
         let mut msg1 = msg.clone();
         msg1.voting_period = Expiration::AtHeight(15);
         match msg1.validate(env.clone()) {
             Ok(_) => panic!("expected error"),
             Err(ContractError::VotingPeriodExpired {}) => {}
-            Err(err) => println!("{:?}", err),
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
 
         let mut msg2 = msg.clone();
@@ -97,13 +402,55 @@ mod tests {
         match msg2.validate(env.clone()) {
             Ok(_) => panic!("expected error"),
             Err(ContractError::ProposalPeriodExpired {}) => {}
-            Err(err) => println!("{:?}", err),
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let mut msg3 = msg.clone();
+        msg3.title_length = Some(LengthLimit { min: 10, max: 5 });
+        match msg3.validate(env.clone()) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidLengthLimit { min: 10, max: 5 }) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
 
-        let msg3 = msg.clone();
-        match msg3.validate(env) {
-            Ok(_) => {}
-            Err(err) => println!("{:?}", err),
+        let mut msg4 = msg.clone();
+        msg4.proposal_deposit = Some(Coin::new(0, "ucosm"));
+        match msg4.validate(env.clone()) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::ZeroProposalDeposit {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let mut msg5 = msg.clone();
+        msg5.vote_proposal_whitelist = Some(vec![]);
+        msg5.voter_group = Some(HumanAddr::from("group"));
+        match msg5.validate(env.clone()) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::WhitelistAndGroupConflict {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let mut msg6 = msg.clone();
+        msg6.voter_weight = Some(VoterWeightSource::Cw4Group {});
+        match msg6.validate(env.clone()) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::VoterGroupRequired {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
+        }
+
+        let msg7 = msg.clone();
+        msg7.validate(env.clone()).unwrap();
+
+        let mut msg8 = msg.clone();
+        msg8.voting_period = Expiration::AtHeight(50);
+        msg8.commit_reveal = Some(CommitReveal {
+            reveal_period: Expiration::AtHeight(40),
+            refund_unrevealed: true,
+        });
+        match msg8.validate(env) {
+            Ok(_) => panic!("expected error"),
+            Err(ContractError::InvalidRevealPeriod {}) => {}
+            e => panic!("unexpected error, got {}", e.unwrap_err()),
         }
     }
 }
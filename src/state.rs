@@ -1,8 +1,9 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-use crate::matching::QuadraticFundingAlgorithm;
-use cosmwasm_std::{Binary, CanonicalAddr, Coin, Storage, Uint128};
+use crate::matching::{CalculatedGrant, QuadraticFundingAlgorithm};
+use cosmwasm_std::{Binary, CanonicalAddr, Coin, Decimal, Empty, Storage, Uint128};
 use cosmwasm_storage::{singleton, Singleton};
 use cw0::Expiration;
 use cw_storage_plus::{Item, Map, U64Key};
@@ -10,19 +11,235 @@ use cw_storage_plus::{Item, Map, U64Key};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     // set admin as single address, multisig or contract sig could be used
-    pub admin: CanonicalAddr,
+    // none if admin is renounced
+    pub admin: Option<CanonicalAddr>,
+    // proposed admin, becomes admin once accepted
+    pub pending_admin: Option<CanonicalAddr>,
     // leftover coins from distribution sent to this address
     pub leftover_addr: CanonicalAddr,
-    pub create_proposal_whitelist: Option<Vec<CanonicalAddr>>,
-    pub vote_proposal_whitelist: Option<Vec<CanonicalAddr>>,
+    // whitelist members are stored under CREATE_PROPOSAL_WHITELIST and VOTE_PROPOSAL_WHITELIST
+    pub create_proposal_whitelist_enabled: bool,
+    pub vote_proposal_whitelist_enabled: bool,
+    // cw4 group contracts used as proposer and voter registries instead of whitelists
+    pub proposer_group: Option<CanonicalAddr>,
+    pub voter_group: Option<CanonicalAddr>,
+    // multiplies square rooted contributions in matching, neutral if not set
+    pub voter_weight: Option<VoterWeightSource>,
+    // votes below min_contribution are rejected
+    pub min_contribution: Uint128,
+    // proposals with fewer contributors are not matched, direct contributions are still paid
+    pub min_unique_contributors: u64,
+    // contributions above max_matched_contribution are paid directly but matched only up to it
+    pub max_matched_contribution: Option<Uint128>,
     pub voting_period: Expiration,
     pub proposal_period: Expiration,
     pub budget: Coin,
     pub algorithm: QuadraticFundingAlgorithm,
+    // byte length bounds of proposal fields
+    pub title_length: LengthLimit,
+    pub description_length: LengthLimit,
+    pub metadata_length: LengthLimit,
+    // deposit required to create a proposal, slashed if proposal is rejected
+    pub proposal_deposit: Option<Coin>,
+    // set by admin to freeze the round
+    #[serde(default)]
+    pub paused: bool,
+    // block the round was paused at, open periods are extended by the paused duration on unpause
+    #[serde(default)]
+    pub paused_at: Option<PausedAt>,
+    // keeps distribution leftover in the contract for the next round's budget
+    #[serde(default)]
+    pub rollover_leftover: bool,
+    // current round, incremented by StartNextRound
+    #[serde(default = "first_round")]
+    pub round: u64,
+    // set once the current round is distributed
+    #[serde(default)]
+    pub distributed: bool,
+    // prices votes in other denoms instead of admin set rates
+    #[serde(default)]
+    pub oracle: Option<CanonicalAddr>,
+    // votes are committed as hashes and revealed after voting, public if not set
+    #[serde(default)]
+    pub commit_reveal: Option<CommitReveal>,
+    // most payout recipients a proposal can split its grant between
+    #[serde(default = "default_max_recipients")]
+    pub max_recipients: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CommitReveal {
+    // committed votes are revealed after voting_period until reveal_period, which must end later
+    pub reveal_period: Expiration,
+    // unrevealed funds are refunded to voters if set, sent to leftover_addr otherwise
+    pub refund_unrevealed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PausedAt {
+    pub height: u64,
+    // seconds, like Expiration::AtTime
+    pub time: u64,
+}
+
+fn first_round() -> u64 {
+    1
+}
+
+fn default_max_recipients() -> u64 {
+    DEFAULT_MAX_RECIPIENTS
+}
+
+impl Config {
+    pub fn is_admin(&self, addr: &CanonicalAddr) -> bool {
+        self.admin.as_ref() == Some(addr)
+    }
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
+// budget units per unit of denoms accepted for votes besides the budget denom
+pub const DENOM_RATES: Map<&[u8], Decimal> = Map::new("denom_rates");
+
+// leftover of a distributed round kept for the next round's budget
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Rollover {
+    pub round: u64,
+    pub amount: Coin,
+    pub height: u64,
+}
+
+pub const ROLLOVERS: Map<U64Key, Rollover> = Map::new("rollovers");
+
+// outcome of a round's distribution, kept after payout for auditing
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DistributionResult {
+    pub round: u64,
+    pub algorithm: QuadraticFundingAlgorithm,
+    pub grants: Vec<Grant>,
+    pub leftover: Coin,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Grant {
+    pub proposal_id: u64,
+    pub addr: CanonicalAddr,
+    pub grant: Uint128,
+    pub collected_vote_funds: Uint128,
+}
+
+impl From<CalculatedGrant> for Grant {
+    fn from(g: CalculatedGrant) -> Self {
+        Grant {
+            proposal_id: g.proposal_id,
+            addr: g.addr,
+            grant: Uint128(g.grant),
+            collected_vote_funds: Uint128(g.collected_vote_funds),
+        }
+    }
+}
+
+pub const DISTRIBUTION_RESULTS: Map<U64Key, DistributionResult> = Map::new("distribution_results");
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Whitelist {
+    CreateProposal,
+    VoteProposal,
+}
+
+impl fmt::Display for Whitelist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Whitelist::CreateProposal => write!(f, "create_proposal"),
+            Whitelist::VoteProposal => write!(f, "vote_proposal"),
+        }
+    }
+}
+
+impl Whitelist {
+    pub fn members(&self) -> &'static Map<'static, &'static [u8], Empty> {
+        match self {
+            Whitelist::CreateProposal => &CREATE_PROPOSAL_WHITELIST,
+            Whitelist::VoteProposal => &VOTE_PROPOSAL_WHITELIST,
+        }
+    }
+
+    pub fn is_enabled(&self, config: &Config) -> bool {
+        match self {
+            Whitelist::CreateProposal => config.create_proposal_whitelist_enabled,
+            Whitelist::VoteProposal => config.vote_proposal_whitelist_enabled,
+        }
+    }
+
+    pub fn group<'a>(&self, config: &'a Config) -> Option<&'a CanonicalAddr> {
+        match self {
+            Whitelist::CreateProposal => config.proposer_group.as_ref(),
+            Whitelist::VoteProposal => config.voter_group.as_ref(),
+        }
+    }
+}
+
+pub const CREATE_PROPOSAL_WHITELIST: Map<&[u8], Empty> = Map::new("create_proposal_whitelist");
+pub const VOTE_PROPOSAL_WHITELIST: Map<&[u8], Empty> = Map::new("vote_proposal_whitelist");
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoterWeightSource {
+    // cw4 member weight in voter_group when the vote is cast
+    Cw4Group {},
+    // admin uploaded scores, unscored voters get neutral weight
+    Scores {},
+}
+
+// voter weight percentages, see matching::WEIGHT_SCALE
+pub const VOTER_SCORES: Map<&[u8], u64> = Map::new("voter_scores");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LengthLimit {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl LengthLimit {
+    pub fn contains(&self, len: usize) -> bool {
+        let len = len as u64;
+        self.min <= len && len <= self.max
+    }
+}
+
+pub const DEFAULT_TITLE_LENGTH: LengthLimit = LengthLimit { min: 1, max: 128 };
+pub const DEFAULT_DESCRIPTION_LENGTH: LengthLimit = LengthLimit { min: 0, max: 4096 };
+pub const DEFAULT_METADATA_LENGTH: LengthLimit = LengthLimit { min: 0, max: 4096 };
+pub const DEFAULT_MAX_RECIPIENTS: u64 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Active,
+    // rejected proposals can't be voted, votes are refunded on distribution
+    Rejected,
+    // hidden proposals are kept out of listings but still take part in the round
+    Hidden,
+}
+
+impl Default for ProposalStatus {
+    fn default() -> Self {
+        ProposalStatus::Active
+    }
+}
+
+impl fmt::Display for ProposalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProposalStatus::Active => write!(f, "active"),
+            ProposalStatus::Rejected => write!(f, "rejected"),
+            ProposalStatus::Hidden => write!(f, "hidden"),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Proposal {
     pub id: u64,
@@ -30,13 +247,31 @@ pub struct Proposal {
     pub description: String,
     pub metadata: Option<Binary>,
     pub fund_address: CanonicalAddr,
+    // value of collected votes in budget units
     pub collected_funds: Uint128,
+    // collected funds counted for matching, capped by max_matched_contribution
+    pub matched_funds: Uint128,
+    pub proposer: CanonicalAddr,
+    pub status: ProposalStatus,
+    pub deposit: Option<Coin>,
+    // payout is split among recipients if set, sent to fund_address otherwise
+    #[serde(default)]
+    pub recipients: Vec<Recipient>,
+}
+
+// shares are in basis points and sum up to BASIS_POINTS
+pub const BASIS_POINTS: u64 = 10000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Recipient {
+    pub addr: CanonicalAddr,
+    pub share: u64,
 }
 
 pub const PROPOSALS: Map<U64Key, Proposal> = Map::new("proposal");
 pub const PROPOSAL_SEQ: &[u8] = b"proposal_seq";
 
-pub fn proposal_seq(storage: &mut dyn Storage) -> Singleton<u64> {
+pub fn proposal_seq(storage: &mut dyn Storage) -> Singleton<'_, u64> {
     singleton(storage, PROPOSAL_SEQ)
 }
 
@@ -45,6 +280,55 @@ pub struct Vote {
     pub proposal_id: u64,
     pub voter: CanonicalAddr,
     pub fund: Coin,
+    // fund value in budget units at vote time, votes without it are in budget denom
+    #[serde(default)]
+    pub value: Option<Uint128>,
+    // curator who allocated the vote from voter's delegated funds
+    #[serde(default)]
+    pub curator: Option<CanonicalAddr>,
+    // voter's cw4 group weight at vote time, used in matching instead of the current weight
+    #[serde(default)]
+    pub weight: Option<u64>,
+}
+
+impl Vote {
+    // value of the vote used in matching
+    pub fn budget_value(&self) -> Uint128 {
+        self.value.unwrap_or(self.fund.amount)
+    }
 }
 
+// escrowed vote with a hidden proposal, counted once revealed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Commitment {
+    pub voter: CanonicalAddr,
+    pub commitment: Binary,
+    pub fund: Coin,
+    // fund value in budget units at commit time
+    pub value: Uint128,
+}
+
+// keyed by canonical voter address and commitment
+pub const COMMITMENTS: Map<(&[u8], &[u8]), Commitment> = Map::new("commitments");
+
+// funds a delegator left to a curator to allocate as votes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Delegation {
+    pub delegator: CanonicalAddr,
+    pub curator: CanonicalAddr,
+    // unallocated funds, refunded on revocation or distribution
+    pub fund: Coin,
+}
+
+// keyed by canonical curator address and canonical delegator address
+pub const DELEGATIONS: Map<(&[u8], &[u8]), Delegation> = Map::new("delegations");
+
+// funds deposited for relayed votes, keyed by canonical voter address
+pub const DEPOSITS: Map<&[u8], Coin> = Map::new("deposits");
+// next nonce of relayed votes, keyed by canonical voter address
+pub const RELAY_NONCES: Map<&[u8], u64> = Map::new("relay_nonces");
+
+// keyed by proposal id and canonical voter address
 pub const VOTES: Map<(U64Key, &[u8]), Vote> = Map::new("votes");
+// secondary index of VOTES keyed by canonical voter address and proposal id
+pub const VOTER_VOTES: Map<(&[u8], U64Key), Empty> = Map::new("voter_votes");
@@ -0,0 +1,36 @@
+use crate::matching::QuadraticFundingAlgorithm;
+use crate::msg::InitMsg;
+use cosmwasm_std::{Env, HumanAddr};
+use cw0::Expiration;
+
+// round init msg shared by tests, override fields with struct update syntax
+pub fn mock_init_msg(env: &Env) -> InitMsg {
+    InitMsg {
+        leftover_addr: HumanAddr::from("addr"),
+        algorithm: QuadraticFundingAlgorithm::CapitalConstrainedLiberalRadicalism {
+            parameter: "".to_string(),
+        },
+        admin: HumanAddr::from("admin"),
+        create_proposal_whitelist: None,
+        vote_proposal_whitelist: None,
+        proposer_group: None,
+        voter_group: None,
+        voter_weight: None,
+        min_contribution: None,
+        min_unique_contributors: None,
+        max_matched_contribution: None,
+        voting_period: Expiration::AtHeight(env.block.height + 15),
+        proposal_period: Expiration::AtHeight(env.block.height + 10),
+        budget_denom: String::from("ucosm"),
+        title_length: None,
+        description_length: None,
+        metadata_length: None,
+        max_recipients: None,
+        proposal_deposit: None,
+        rollover_leftover: None,
+        factory: None,
+        accepted_denoms: None,
+        oracle: None,
+        commit_reveal: None,
+    }
+}